@@ -0,0 +1,158 @@
+use crate::{
+    RunResult, Session, badge,
+    report::{self, JsonReport, Summary},
+    shut_down,
+    shutdown::Shutdown,
+};
+use actix_web::{
+    HttpRequest, HttpResponse,
+    http::header::{self, CacheControl, CacheDirective},
+    web,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{io, path::PathBuf};
+use tracing::info;
+
+/// Directory the coverage reports are written to and served from
+pub struct ReportDir(pub PathBuf);
+
+#[derive(Serialize)]
+struct FileCoverage<'a> {
+    path: &'a str,
+    #[serde(flatten)]
+    summary: &'a Summary,
+}
+
+#[derive(Serialize)]
+struct CoverageResponse<'a> {
+    totals: &'a Summary,
+    files: Vec<FileCoverage<'a>>,
+}
+
+fn error(mut response: actix_web::HttpResponseBuilder, message: &str) -> HttpResponse {
+    response.json(json!({ "error": message }))
+}
+
+/// Totals and per-file counts from the latest `coverage json` report
+pub async fn coverage(dir: web::Data<ReportDir>) -> HttpResponse {
+    let bytes = match tokio::fs::read(dir.0.join(report::JSON_REPORT)).await {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return error(
+                HttpResponse::NotFound(),
+                "no coverage run has completed yet",
+            );
+        }
+        Err(e) => return error(HttpResponse::InternalServerError(), &e.to_string()),
+    };
+
+    let report = match JsonReport::parse(&bytes) {
+        Ok(report) => report,
+        Err(e) => return error(HttpResponse::InternalServerError(), &e.to_string()),
+    };
+
+    HttpResponse::Ok().json(CoverageResponse {
+        totals: &report.totals,
+        files: report
+            .files
+            .iter()
+            .map(|(path, file)| FileCoverage {
+                path,
+                summary: &file.summary,
+            })
+            .collect(),
+    })
+}
+
+/// Badge showing the total coverage of the most recent run
+pub async fn badge(session: web::Data<Session>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("image/svg+xml")
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        .body(badge::render(session.last_total()))
+}
+
+/// Readiness check for scripts waiting on the server to come up
+pub async fn health() -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "status": "ok" }))
+}
+
+/// Body of a `POST /api/run` request, which can also be left empty
+#[derive(Default, Deserialize)]
+struct RunRequest {
+    /// Test path to set before running, as typed at the prompt
+    test_path: Option<String>,
+}
+
+/// Whether the request comes from a page served by another site, going by its Origin
+fn is_cross_origin(req: &HttpRequest) -> bool {
+    let Some(origin) = req.headers().get(header::ORIGIN) else {
+        return false;
+    };
+    let host = req.connection_info().host().to_string();
+    origin
+        .to_str()
+        .map_or(true, |origin| !origin.ends_with(&format!("://{}", host)))
+}
+
+/// Run coverage, with the test path from the body if it has one, and answer with the
+/// result once the run finishes. Only routed with `--allow-run`.
+pub async fn run(req: HttpRequest, session: web::Data<Session>, body: web::Bytes) -> HttpResponse {
+    // Any page open in the browser could otherwise start runs
+    if is_cross_origin(&req) {
+        return error(
+            HttpResponse::Forbidden(),
+            "runs can't be started from other sites",
+        );
+    }
+    let request: RunRequest = if body.is_empty() {
+        RunRequest::default()
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(request) => request,
+            Err(e) => return error(HttpResponse::BadRequest(), &e.to_string()),
+        }
+    };
+
+    let session = session.into_inner();
+    let finished = web::block(move || {
+        let result = match &request.test_path {
+            Some(path) => session.run_with_test_path(path),
+            None => session.run(),
+        };
+        (result, session.last_total())
+    })
+    .await;
+    match finished {
+        Ok((Some(result), total)) => HttpResponse::Ok().json(json!({
+            "result": result,
+            // A failed run leaves the previous run's total in place
+            "total": if result == RunResult::Failed { None } else { total },
+        })),
+        Ok((None, _)) => error(
+            HttpResponse::Conflict(),
+            "no run was started: another run is in progress or --dry-run is on",
+        ),
+        Err(e) => error(HttpResponse::InternalServerError(), &e.to_string()),
+    }
+}
+
+/// Shut the tool down gracefully, as Ctrl+C would, answering before the server stops.
+/// Only routed with `--allow-run`.
+pub async fn shutdown(
+    req: HttpRequest,
+    session: web::Data<Session>,
+    shutdown: web::Data<Shutdown>,
+) -> HttpResponse {
+    if is_cross_origin(&req) {
+        return error(
+            HttpResponse::Forbidden(),
+            "the tool can't be shut down from other sites",
+        );
+    }
+    info!("Shutdown requested over HTTP, shutting down...");
+    // The graceful stop lets this response go out first
+    shut_down(&session.into_inner(), &shutdown);
+    HttpResponse::Accepted().json(json!({ "status": "shutting down" }))
+}
@@ -0,0 +1,70 @@
+use actix_web::{
+    HttpRequest, HttpResponse,
+    web::{self, Bytes},
+};
+use rust_embed::RustEmbed;
+use std::path::Path;
+
+/// Route below which the built-in assets are served, out of the way of report files
+pub const ASSETS_PATH: &str = "/__coverage-http/assets";
+
+/// Page written as index.html until the first report is generated
+pub const PLACEHOLDER_PAGE: &str = "placeholder.html";
+
+/// Icon served at `/favicon.ico` unless another one is given
+pub const FAVICON: &str = "favicon.ico";
+
+/// Files built into the binary, so it can be deployed on its own
+#[derive(RustEmbed)]
+#[folder = "assets/"]
+struct Assets;
+
+/// Contents of a built-in asset
+pub fn bytes(name: &str) -> Bytes {
+    match Assets::get(name) {
+        Some(file) => Bytes::from(file.data.into_owned()),
+        None => panic!("asset {} isn't built in", name),
+    }
+}
+
+/// Contents of a built-in text asset
+pub fn text(name: &str) -> String {
+    String::from_utf8_lossy(&bytes(name)).into_owned()
+}
+
+/// Content type of a file going by its extension, if it is one the server knows of
+pub fn content_type(path: &str) -> Option<&'static str> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())?
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "html" => Some("text/html; charset=utf-8"),
+        "css" => Some("text/css; charset=utf-8"),
+        "js" => Some("text/javascript; charset=utf-8"),
+        "ico" => Some("image/x-icon"),
+        "png" => Some("image/png"),
+        "svg" => Some("image/svg+xml"),
+        "gif" => Some("image/gif"),
+        _ => None,
+    }
+}
+
+/// Serve the built-in asset named by the rest of the request path
+async fn serve(req: HttpRequest) -> HttpResponse {
+    let name = req
+        .path()
+        .trim_start_matches(ASSETS_PATH)
+        .trim_start_matches('/');
+    HttpResponse::Ok()
+        .content_type(content_type(name).unwrap_or("application/octet-stream"))
+        .body(bytes(name))
+}
+
+/// Register a route for each built-in asset. Only those exact paths take precedence
+/// over the report, so anything else below [`ASSETS_PATH`] is still looked up there.
+pub fn routes(config: &mut web::ServiceConfig) {
+    for name in Assets::iter() {
+        config.route(&format!("{}/{}", ASSETS_PATH, name), web::get().to(serve));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::Session;
+use actix_web::{
+    Error, HttpResponse,
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web,
+};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use sha2::{Digest, Sha256};
+use std::{fmt, str::FromStr};
+
+/// Username and password required to view the report
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credentials {
+    user: String,
+    password: String,
+}
+
+impl FromStr for Credentials {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((user, password)) if !user.is_empty() => Ok(Self {
+                user: user.to_string(),
+                password: password.to_string(),
+            }),
+            _ => Err("expected USER:PASS".to_string()),
+        }
+    }
+}
+
+impl fmt::Debug for Credentials {
+    /// Leaves the password out so it never ends up in logs
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("user", &self.user)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Credentials {
+    /// Whether the `Authorization` header value carries these credentials
+    fn matches(&self, authorization: &str) -> bool {
+        let Some(encoded) = authorization.strip_prefix("Basic ") else {
+            return false;
+        };
+        let Ok(decoded) = BASE64_STANDARD.decode(encoded.trim()) else {
+            return false;
+        };
+        let expected = format!("{}:{}", self.user, self.password);
+        // Digests are compared in full, so the time taken doesn't tell how much of the
+        // credentials was right, and they have the same length whatever was sent
+        let (given, expected) = (Sha256::digest(&decoded), Sha256::digest(expected));
+        given
+            .iter()
+            .zip(expected.iter())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+    }
+}
+
+/// Middleware rejecting requests without valid Basic credentials when `--auth` is set
+pub async fn require(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody + 'static>>, Error> {
+    let credentials = req
+        .app_data::<web::Data<Session>>()
+        .and_then(|session| session.args.auth.clone());
+    let Some(credentials) = credentials else {
+        return Ok(next.call(req).await?.map_into_left_body());
+    };
+
+    let authorized = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| credentials.matches(value));
+    if authorized {
+        return Ok(next.call(req).await?.map_into_left_body());
+    }
+
+    let response = HttpResponse::Unauthorized()
+        .insert_header((header::WWW_AUTHENTICATE, r#"Basic realm="coverage-http""#))
+        .body("Authentication required");
+    Ok(req.into_response(response).map_into_right_body())
+}
@@ -0,0 +1,41 @@
+/// Approximate width in pixels of a character of the badge font
+const CHAR_WIDTH: usize = 7;
+/// Horizontal padding in pixels around each half of the badge
+const PADDING: usize = 10;
+
+/// Shields-style color for a coverage percentage
+fn color(total: Option<f64>) -> &'static str {
+    match total {
+        None => "#9f9f9f",
+        Some(total) if total < 50.0 => "#e05d44",
+        Some(total) if total < 80.0 => "#dfb317",
+        Some(_) => "#4c1",
+    }
+}
+
+/// Render a flat "coverage" badge for the given total, or "unknown" without one
+pub fn render(total: Option<f64>) -> String {
+    let label = "coverage";
+    let value = total.map_or_else(|| "unknown".to_string(), |total| format!("{:.0}%", total));
+
+    let label_width = label.len() * CHAR_WIDTH + PADDING;
+    let value_width = value.len() * CHAR_WIDTH + PADDING;
+    let width = label_width + value_width;
+    let label_x = label_width / 2;
+    let value_x = label_width + value_width / 2;
+    let color = color(total);
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {value}">
+<title>{label}: {value}</title>
+<linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>
+<clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+<g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{value_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g>
+<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+<text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text><text x="{label_x}" y="14">{label}</text>
+<text x="{value_x}" y="15" fill="#010101" fill-opacity=".3">{value}</text><text x="{value_x}" y="14">{value}</text>
+</g>
+</svg>
+"##
+    )
+}
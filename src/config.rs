@@ -0,0 +1,116 @@
+use crate::{Args, TestRunner};
+use clap::{ArgMatches, parser::ValueSource};
+use serde::Deserialize;
+use std::{fs, io};
+use tracing::warn;
+
+/// Per-project config file, read from the current directory
+pub const CONFIG_FILE: &str = "coverage-http.toml";
+
+/// Keys understood in the config file; anything else is warned about and ignored
+const KNOWN_KEYS: &[&str] = &[
+    "port",
+    "host",
+    "dir",
+    "python",
+    "test_path",
+    "runner",
+    "fail_under",
+    "runs",
+];
+
+/// Settings from the config file, each overridden by its command-line flag
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    port: Option<u16>,
+    host: Option<String>,
+    dir: Option<String>,
+    python: Option<String>,
+    test_path: Option<String>,
+    runner: Option<TestRunner>,
+    fail_under: Option<f64>,
+    /// Arguments of each `coverage run` invocation, as typed in a shell
+    runs: Option<Vec<String>>,
+}
+
+impl Config {
+    /// Load the config file, if there is one
+    pub fn load() -> io::Result<Option<Self>> {
+        let contents = match fs::read_to_string(CONFIG_FILE) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let invalid = |e: toml::de::Error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", CONFIG_FILE, e),
+            )
+        };
+
+        let table: toml::Table = contents.parse().map_err(invalid)?;
+        for key in table.keys() {
+            if !KNOWN_KEYS.contains(&key.as_str()) {
+                warn!("Warning: ignoring unknown key `{}` in {}", key, CONFIG_FILE);
+            }
+        }
+
+        let config: Self = toml::from_str(&contents).map_err(invalid)?;
+        for run in config.runs.iter().flatten() {
+            if shlex::split(run).is_none_or(|words| words.is_empty()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: can't split run `{}` into arguments", CONFIG_FILE, run),
+                ));
+            }
+        }
+        Ok(Some(config))
+    }
+
+    /// Fill in the arguments that weren't given on the command line, falling back to the
+    /// runner's report directory when the config file doesn't set one either
+    pub fn apply(self, args: &mut Args, matches: &ArgMatches) {
+        let unset = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+
+        if let Some(port) = self.port
+            && unset("port")
+        {
+            args.port = port;
+        }
+        if let Some(host) = self.host
+            && unset("host")
+        {
+            args.host = host;
+        }
+        if let Some(python) = self.python
+            && unset("python")
+        {
+            args.python = Some(python);
+        }
+        if let Some(test_path) = self.test_path
+            && unset("test_path")
+        {
+            args.test_path = Some(test_path);
+        }
+        if let Some(runner) = self.runner
+            && unset("runner")
+        {
+            args.runner = runner;
+        }
+        // After the runner, which decides where the report goes by default
+        if unset("dir") {
+            args.dir = self
+                .dir
+                .unwrap_or_else(|| args.runner.default_dir().to_string());
+        }
+        if let Some(fail_under) = self.fail_under
+            && unset("fail_under")
+        {
+            args.fail_under = Some(fail_under);
+        }
+        // Checked to split when loaded
+        if let Some(runs) = self.runs {
+            args.runs = runs.iter().filter_map(|run| shlex::split(run)).collect();
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{RunResult, live_reload::LiveEvent, runner::strip_ansi};
+use actix_web::{
+    Error, HttpResponse,
+    http::header::{CacheControl, CacheDirective, ContentEncoding},
+    web::{self, Bytes},
+};
+use futures_util::stream;
+use serde_json::json;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Route of the Server-Sent Events stream of the output of runs
+pub const STREAM_PATH: &str = "/api/stream";
+
+/// Lines kept for consoles that fall behind before they miss some
+pub const CAPACITY: usize = 1024;
+
+/// Which output of a command a line was written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// What a run sends to the connected consoles as it goes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleEvent {
+    /// A command of the run is starting
+    Command(String),
+    /// A line of output, without colors or the line ending
+    Line(Stream, String),
+    /// The run is over
+    Finished(RunResult),
+}
+
+impl ConsoleEvent {
+    /// The event as Server-Sent Events text. The data is JSON, so a line holding a
+    /// carriage return doesn't end the event early.
+    fn to_sse(&self) -> String {
+        let (name, data) = match self {
+            ConsoleEvent::Command(command) => ("command", json!(command)),
+            ConsoleEvent::Line(Stream::Stdout, line) => ("stdout", json!(line)),
+            ConsoleEvent::Line(Stream::Stderr, line) => ("stderr", json!(line)),
+            ConsoleEvent::Finished(result) => ("finished", json!(result)),
+        };
+        format!("event: {}\ndata: {}\n\n", name, data)
+    }
+}
+
+/// Splits the output of one stream of a command into lines for the consoles
+pub struct LineSender {
+    sender: broadcast::Sender<ConsoleEvent>,
+    stream: Stream,
+    partial: Vec<u8>,
+}
+
+impl LineSender {
+    pub fn new(sender: broadcast::Sender<ConsoleEvent>, stream: Stream) -> Self {
+        Self {
+            sender,
+            stream,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Send the lines completed by `output`, keeping the rest for the next call
+    pub fn write(&mut self, output: &[u8]) {
+        self.partial.extend_from_slice(output);
+        while let Some(end) = self.partial.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            self.send(&line);
+        }
+    }
+
+    /// Send what is left of an unterminated last line
+    pub fn finish(mut self) {
+        if !self.partial.is_empty() {
+            let line = std::mem::take(&mut self.partial);
+            self.send(&line);
+        }
+    }
+
+    fn send(&self, line: &[u8]) {
+        let line = String::from_utf8_lossy(line);
+        let line = strip_ansi(line.trim_end_matches(['\n', '\r']));
+        // Nobody listening just means no console is open
+        let _ = self.sender.send(ConsoleEvent::Line(self.stream, line));
+    }
+}
+
+/// Stream the output of runs to a browser page as Server-Sent Events, from the moment
+/// it connects, until the server shuts down
+pub async fn stream(
+    console: web::Data<broadcast::Sender<ConsoleEvent>>,
+    events: web::Data<broadcast::Sender<LiveEvent>>,
+) -> HttpResponse {
+    let receivers = (console.subscribe(), events.subscribe());
+    let stream = stream::unfold(receivers, |(mut console, mut events)| async move {
+        loop {
+            tokio::select! {
+                event = console.recv() => match event {
+                    Ok(event) => {
+                        let message = Bytes::from(event.to_sse());
+                        return Some((Ok::<_, Error>(message), (console, events)));
+                    }
+                    // Lines that were missed are gone, so carry on with the next ones
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                },
+                event = events.recv() => match event {
+                    Ok(LiveEvent::Shutdown) | Err(RecvError::Closed) => return None,
+                    Ok(LiveEvent::Reload) | Err(RecvError::Lagged(_)) => continue,
+                },
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        // Compression would buffer lines instead of sending them right away
+        .insert_header(ContentEncoding::Identity)
+        .streaming(stream)
+}
@@ -0,0 +1,43 @@
+use crate::assets;
+use actix_web::{
+    HttpResponse,
+    http::header::{CacheControl, CacheDirective},
+    web::{self, Bytes},
+};
+use std::{fs, io};
+
+/// Icon served at `/favicon.ico`, so browsers asking for it don't get a 404
+#[derive(Clone)]
+pub struct Favicon {
+    bytes: Bytes,
+    content_type: &'static str,
+}
+
+impl Favicon {
+    /// The icon in the given file, or the built-in one without a file. The content type
+    /// follows the file extension, so PNG and SVG icons work too.
+    pub fn load(path: Option<&str>) -> io::Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self {
+                bytes: assets::bytes(assets::FAVICON),
+                content_type: "image/x-icon",
+            });
+        };
+
+        let bytes = fs::read(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("reading favicon {}: {}", path, e)))?;
+        let content_type = assets::content_type(path).unwrap_or("image/x-icon");
+        Ok(Self {
+            bytes: Bytes::from(bytes),
+            content_type,
+        })
+    }
+}
+
+pub async fn serve(favicon: web::Data<Favicon>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(favicon.content_type)
+        // Unlike the report, the icon doesn't change while the server runs
+        .insert_header(CacheControl(vec![CacheDirective::MaxAge(86400)]))
+        .body(favicon.bytes.clone())
+}
@@ -0,0 +1,16 @@
+use std::process::{Command, Stdio};
+
+/// Abbreviated hash of the commit checked out in the working directory, or `None`
+/// outside a git repository, before its first commit or without git installed
+pub fn short_head() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let hash = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!hash.is_empty()).then_some(hash)
+}
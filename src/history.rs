@@ -0,0 +1,109 @@
+use crate::report::MIN_VISIBLE_DELTA;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+/// File name of the coverage history, stored in the report directory
+pub const HISTORY_FILE: &str = "history.jsonl";
+
+/// Total coverage recorded after one run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub timestamp: DateTime<Utc>,
+    pub total: f64,
+    /// Abbreviated hash of the commit the run tested, if in a git repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Seconds the run took, for entries recorded since this was tracked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+}
+
+/// Append an entry to the history in `dir`, creating the file if needed, and drop the
+/// oldest entries beyond `max_entries`
+pub fn append(dir: &Path, entry: &Entry, max_entries: usize) -> io::Result<()> {
+    let path = dir.join(HISTORY_FILE);
+    let line = serde_json::to_string(entry)?;
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+
+    let lines: Vec<&str> = contents.lines().collect();
+    if lines.len() < max_entries {
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        return writeln!(file, "{}", line);
+    }
+
+    // The pruned history is written next to the file and renamed over it, so a crash
+    // halfway leaves either the old history or the new one, never a truncated file
+    let kept = &lines[lines.len() + 1 - max_entries..];
+    let temporary = dir.join(format!("{}.tmp", HISTORY_FILE));
+    let mut file = fs::File::create(&temporary)?;
+    for kept_line in kept {
+        writeln!(file, "{}", kept_line)?;
+    }
+    writeln!(file, "{}", line)?;
+    file.sync_all()?;
+    fs::rename(&temporary, &path)
+}
+
+/// Load the history in `dir`, oldest first. A missing file is an empty history and
+/// unreadable lines are skipped.
+pub fn load(dir: &Path) -> io::Result<Vec<Entry>> {
+    let contents = match fs::read_to_string(dir.join(HISTORY_FILE)) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Print the last `count` entries along with how each compares to the run before it
+pub fn print_recent(entries: &[Entry], count: usize) {
+    if entries.is_empty() {
+        println!("No coverage history yet");
+        return;
+    }
+
+    let start = entries.len().saturating_sub(count);
+    for (i, entry) in entries.iter().enumerate().skip(start) {
+        let timestamp = entry.timestamp.with_timezone(&Local);
+        let trend = match i
+            .checked_sub(1)
+            .map(|previous| entry.total - entries[previous].total)
+        {
+            // Smaller changes would round to 0.0, as after a run
+            Some(delta) if delta >= MIN_VISIBLE_DELTA => format!("  up {:+.1}%", delta),
+            Some(delta) if delta <= -MIN_VISIBLE_DELTA => format!("  down {:+.1}%", delta),
+            Some(_) => "  unchanged".to_string(),
+            None => String::new(),
+        };
+        let commit = entry
+            .commit
+            .as_ref()
+            .map(|commit| format!("  {}", commit))
+            .unwrap_or_default();
+        let duration = entry
+            .duration
+            .map(|duration| format!("  in {:.1}s", duration))
+            .unwrap_or_default();
+        println!(
+            "{}  {:5.1}%{}{}{}",
+            timestamp.format("%Y-%m-%d %H:%M:%S"),
+            entry.total,
+            commit,
+            duration,
+            trend
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use actix_web::{
+    Error, HttpResponse,
+    body::{self, BoxBody, MessageBody},
+    dev::ServiceResponse,
+    error::ErrorInternalServerError,
+    http::{
+        StatusCode,
+        header::{self, CacheControl, CacheDirective, ContentEncoding},
+    },
+    web::{self, Bytes},
+};
+use futures_util::stream;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Route of the Server-Sent Events stream that served pages listen to
+pub const EVENTS_PATH: &str = "/__coverage-http/events";
+
+/// Snippet injected into served HTML pages to reload them when asked to
+const RELOAD_SCRIPT: &str = r#"<script>
+new EventSource("/__coverage-http/events").onmessage = function (event) {
+    if (event.data === "reload") location.reload();
+};
+</script>"#;
+
+/// Event broadcast from the REPL and the server to connected browser pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveEvent {
+    /// A coverage run completed and the report should be reloaded
+    Reload,
+    /// The server is shutting down and event streams should be closed
+    Shutdown,
+}
+
+/// Stream live events to a browser page as Server-Sent Events
+pub async fn events(sender: web::Data<broadcast::Sender<LiveEvent>>) -> HttpResponse {
+    let receiver = sender.subscribe();
+    let stream = stream::unfold(receiver, |mut receiver| async move {
+        match receiver.recv().await {
+            // A lagged receiver missed at least one reload, so reload anyway
+            Ok(LiveEvent::Reload) | Err(RecvError::Lagged(_)) => {
+                let message = Bytes::from_static(b"data: reload\n\n");
+                Some((Ok::<_, Error>(message), receiver))
+            }
+            Ok(LiveEvent::Shutdown) | Err(RecvError::Closed) => None,
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        // Compression would buffer events instead of sending them right away
+        .insert_header(ContentEncoding::Identity)
+        .streaming(stream)
+}
+
+/// Insert the reload script into successful HTML responses, leaving others untouched
+pub async fn inject_script<B>(res: ServiceResponse<B>) -> Result<ServiceResponse<BoxBody>, Error>
+where
+    B: MessageBody + 'static,
+{
+    let is_html = res.status() == StatusCode::OK
+        && res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/html"));
+    if !is_html {
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let (req, res) = res.into_parts();
+    let (mut res, body) = res.into_parts();
+    let bytes = body::to_bytes(body).await.map_err(|e| {
+        let e: Box<dyn std::error::Error> = e.into();
+        ErrorInternalServerError(e.to_string())
+    })?;
+
+    let bytes = match String::from_utf8(bytes.to_vec()) {
+        Ok(mut html) => {
+            let position = html.to_ascii_lowercase().rfind("</body>");
+            html.insert_str(position.unwrap_or(html.len()), RELOAD_SCRIPT);
+            Bytes::from(html)
+        }
+        Err(_) => bytes,
+    };
+
+    res.headers_mut().remove(header::CONTENT_LENGTH);
+    Ok(ServiceResponse::new(req, res.set_body(BoxBody::new(bytes))))
+}
@@ -0,0 +1,85 @@
+use clap::ValueEnum;
+use std::{
+    fmt,
+    io::{self, IsTerminal},
+};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    fmt::{FmtContext, FormatEvent, FormatFields, format::Writer, writer::MakeWriterExt},
+    prelude::*,
+    registry::LookupSpan,
+};
+
+/// Target of the access log written by actix's `Logger` middleware
+const ACCESS_LOG_TARGET: &str = "actix_web::middleware::logger";
+
+/// Most detailed kind of message printed
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    /// Also timestamps each message and includes the libraries' own messages
+    Debug,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+        }
+    }
+}
+
+/// Prints just the message, the way the tool always has
+struct Plain;
+
+impl<S, N> FormatEvent<S, N> for Plain
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+/// Print messages up to `level`, warnings and errors to stderr and the rest to stdout.
+/// Messages from libraries are only printed at the debug level, except for the access
+/// log, which is printed whenever `access_log` is set. Messages logged with the `log`
+/// crate, as actix does, are printed the same way.
+pub fn init(level: LogLevel, access_log: bool) {
+    let writer = io::stderr.with_max_level(Level::WARN).or_else(io::stdout);
+    let access_level = if access_log {
+        LevelFilter::INFO
+    } else {
+        LevelFilter::OFF
+    };
+    let filter = Targets::new()
+        .with_target(env!("CARGO_CRATE_NAME"), level)
+        .with_target(ACCESS_LOG_TARGET, access_level);
+
+    let registry = tracing_subscriber::registry();
+    if level == LogLevel::Debug {
+        let filter = filter.with_default(LevelFilter::DEBUG);
+        let layer = tracing_subscriber::fmt::layer()
+            .with_writer(writer)
+            .with_ansi(io::stdout().is_terminal());
+        registry.with(layer.with_filter(filter)).init();
+    } else {
+        let layer = tracing_subscriber::fmt::layer()
+            .with_writer(writer)
+            .event_format(Plain);
+        registry.with(layer.with_filter(filter)).init();
+    }
+}
@@ -1,247 +1,2197 @@
+mod api;
+mod assets;
+mod auth;
+mod badge;
+mod config;
+mod console;
+mod favicon;
+mod git;
+mod history;
+mod live_reload;
+mod logging;
+mod notification;
+mod progress;
+mod project;
+mod repl;
+mod report;
+mod run_summary;
+mod runner;
+mod shutdown;
+mod snapshot;
+mod state;
+mod tls;
+mod watch;
+
 use actix_files as fs;
-use actix_web::{App, HttpServer};
+use actix_web::{
+    App, HttpResponse, HttpServer,
+    dev::Service,
+    http::header::{CacheControl, CacheDirective, ContentType},
+    middleware::{Compress, Condition, DefaultHeaders, Logger, from_fn},
+    web,
+};
+use chrono::{DateTime, Local, Utc};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum, error::ErrorKind};
+use config::Config;
+use console::{ConsoleEvent, LineSender};
+use live_reload::LiveEvent;
+use progress::Progress;
+use report::JsonReport;
+use runner::CoverageRunner;
+use serde::{Deserialize, Serialize};
+use shutdown::Shutdown;
 use std::{
-    fs as std_fs,
-    io::{self, Write},
-    path::Path,
-    process::{self, Command, Stdio},
-    sync::{
-        Arc,
-        atomic::{AtomicBool, Ordering},
-    },
+    fmt, fs as std_fs,
+    io::{self, IsTerminal, Read, Write},
+    iter,
+    net::{IpAddr, Ipv6Addr, TcpListener},
+    path::{Component, Path, PathBuf},
+    process::{self, Child, Command, ExitCode, ExitStatus, Stdio},
+    sync::{Arc, Mutex, MutexGuard, TryLockError},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
+use tokio::sync::broadcast;
+use tracing::{error, info, info_span, warn};
 
-async fn start_http_server(html_dir: &str, running: Arc<AtomicBool>) -> io::Result<()> {
-    println!(
-        "Starting HTTP server on http://localhost:8080\nNavigate to this URL to view coverage reports"
-    );
+/// Serve Python coverage HTML reports and re-run tests on demand
+#[derive(Parser, Debug, Clone)]
+#[command(version, about)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+
+    /// Address for the HTTP server to bind to
+    #[arg(long, global = true, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Port for the HTTP server to listen on
+    #[arg(long, global = true, default_value_t = 8080)]
+    port: u16,
+
+    /// Times to retry binding to the requested port while it is in use, with a short
+    /// backoff, before moving on to the next port
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    bind_retries: u32,
+
+    /// Serve on this Unix domain socket instead of a TCP port, for a reverse proxy in
+    /// front of the tool; the socket file is removed on shutdown
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["tls", "open"])]
+    unix_socket: Option<PathBuf>,
+
+    /// Directory containing the coverage HTML reports [default: htmlcov,
+    /// target/llvm-cov/html with the llvm-cov runner or coverage/lcov-report with jest]
+    #[arg(
+        long,
+        global = true,
+        default_value = "htmlcov",
+        hide_default_value = true
+    )]
+    dir: String,
+
+    /// Python interpreter used to run coverage [default: python, or python3 if only that is
+    /// on the PATH]
+    #[arg(long, global = true)]
+    python: Option<String>,
+
+    /// Don't prefer the interpreter of a .venv or venv directory in the current directory
+    #[arg(long)]
+    no_venv: bool,
+
+    /// Don't run Python through `poetry run` or `pipenv run` when a poetry.lock or
+    /// Pipfile.lock is found
+    #[arg(long)]
+    no_poetry: bool,
+
+    /// Open the report in the default browser once the server is up
+    #[arg(long)]
+    open: bool,
+
+    /// Don't inject the script that reloads served pages after each coverage run
+    #[arg(long)]
+    no_live_reload: bool,
+
+    /// Test path used until another one is entered at the prompt [default: the last one
+    /// used, the testpaths from the pytest configuration, or .]
+    #[arg(long, value_name = "PATH")]
+    test_path: Option<String>,
+
+    /// Don't remember the test path or the prompt history between sessions
+    #[arg(long)]
+    no_persist: bool,
+
+    /// Test runner, and with it the coverage tool, used for a run
+    #[arg(long, value_enum, default_value_t = TestRunner::Pytest)]
+    runner: TestRunner,
+
+    /// Commands to run instead of the runner's own, joined with &&. {path}, {python} and
+    /// {dir} are replaced with the test path, interpreter and report directory.
+    #[arg(long, value_name = "TEMPLATE")]
+    cmd_template: Option<runner::CommandTemplate>,
+
+    /// Extra arguments appended verbatim to the test runner command
+    #[arg(last = true, value_name = "RUNNER_ARGS")]
+    runner_args: Vec<String>,
+
+    /// Exit at startup if coverage or the test runner isn't installed, instead of warning
+    #[arg(long)]
+    strict: bool,
+
+    /// Treat a total coverage below this percentage as a failed run
+    #[arg(long, value_name = "PCT")]
+    fail_under: Option<f64>,
+
+    /// Erase the coverage data of previous runs before each run (coverage.py runners only)
+    #[arg(long)]
+    erase: bool,
+
+    /// Run the tests on this many pytest-xdist workers, measured with pytest-cov (pytest
+    /// runner only)
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with_all = ["context", "exclude"]
+    )]
+    jobs: Option<u32>,
+
+    /// Also write a Cobertura XML report after each run (coverage.py runners only)
+    #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "coverage.xml")]
+    xml: Option<String>,
+
+    /// Also write an LCOV report after each run (coverage.py runners only)
+    #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "coverage.lcov")]
+    lcov: Option<String>,
+
+    /// Print GitHub Actions warnings for newly uncovered lines after each run, so they
+    /// show up as annotations; on by default inside GitHub Actions
+    #[arg(long, env = "GITHUB_ACTIONS", hide_env_values = true)]
+    github: bool,
+
+    /// Number of files with the lowest coverage to list after each run, 0 for none
+    #[arg(long, value_name = "N", default_value_t = 5)]
+    worst: usize,
+
+    /// Measure branch coverage as well as line coverage (coverage.py runners only)
+    #[arg(long)]
+    branch: bool,
+
+    /// Attribute the coverage of each run to this coverage.py context, shown in the
+    /// report (coverage.py runners only)
+    #[arg(long, value_name = "LABEL")]
+    context: Option<String>,
+
+    /// Separate `coverage run` invocations, split into words, to run instead of the test
+    /// runner and combine into one report; only set from the config file
+    #[arg(skip)]
+    runs: Vec<Vec<String>>,
+
+    /// Leave files matching this glob out of the coverage data, like coverage's --omit
+    /// (repeatable; coverage.py runners only)
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Number of runs kept in the coverage history, dropping the oldest
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 100,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    max_history: u64,
+
+    /// Write the outcome, total, per-file coverage, test counts, duration and commit of
+    /// each run to this JSON file
+    #[arg(long, value_name = "PATH")]
+    summary_file: Option<PathBuf>,
+
+    /// Keep a copy of the report of each successful run, served under /snapshots/, up to
+    /// this many, deleting the oldest
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    snapshots: Option<u64>,
+
+    /// Also write the output of each run's commands to this file, replacing the previous
+    /// run's
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<String>,
+
+    /// Don't show a spinner and the elapsed time while a command runs
+    #[arg(long)]
+    no_progress: bool,
+
+    /// Accept POST /api/run to start a run and POST /api/shutdown to stop the tool, from
+    /// the browser or a script. Any client that can reach the server (and pass --auth) can
+    /// then execute the coverage commands.
+    #[arg(long)]
+    allow_run: bool,
+
+    /// Show a desktop notification with the outcome of each run when it finishes
+    #[arg(long)]
+    notify: bool,
+
+    /// Print the commands a run would execute instead of executing them
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Run coverage once for the current test path instead of prompting, then keep serving
+    #[arg(long)]
+    once: bool,
+
+    /// Run coverage once, then keep serving the report until Ctrl+C without prompting,
+    /// so stdin is never read (same as --once)
+    #[arg(long, conflicts_with = "exit")]
+    serve_after: bool,
+
+    /// With --once, exit right after the run instead of serving the report
+    #[arg(long, requires = "once")]
+    exit: bool,
+
+    /// Seconds to wait before the first run with --once or in watch mode, for things
+    /// the tests need that come up together with this tool
+    #[arg(long, value_name = "SECS", default_value_t = 0)]
+    delay: u64,
+
+    /// Serve responses uncompressed even when the browser accepts gzip or brotli
+    #[arg(long)]
+    no_compress: bool,
+
+    /// Always send the full report instead of letting the browser revalidate its cached
+    /// copy
+    #[arg(long)]
+    no_cache: bool,
+
+    /// List the files of report directories that have no index.html instead of
+    /// answering with a 404
+    #[arg(long)]
+    list: bool,
+
+    /// Re-run coverage whenever a file matching this glob changes
+    #[arg(long, value_name = "GLOB", num_args = 0..=1, default_missing_value = "**/*.py")]
+    watch: Option<String>,
+
+    /// Only re-run for changes to files with these extensions in watch mode, comma
+    /// separated [default: py, rs with the llvm-cov runner or js,jsx,ts,tsx with jest]
+    #[arg(long, value_name = "EXTS", value_delimiter = ',')]
+    watch_ext: Vec<String>,
+
+    /// Never re-run for changes to files matching this glob in watch mode, on top of
+    /// virtualenvs, __pycache__ and .git (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    watch_ignore: Vec<String>,
+
+    /// Re-run for changes to files ignored by the .gitignore in watch mode too
+    #[arg(long)]
+    no_gitignore: bool,
+
+    /// Milliseconds to wait for a burst of file changes to settle in watch mode
+    #[arg(long, value_name = "MS", default_value_t = 500)]
+    debounce: u64,
+
+    /// Seconds that must pass after a run before a change starts another in watch mode
+    #[arg(long, value_name = "SECS", default_value_t = 0)]
+    watch_min_interval: u64,
+
+    /// Serve over HTTPS with a self-signed certificate generated at startup
+    #[arg(long)]
+    tls: bool,
+
+    /// Require these HTTP Basic credentials to view the report
+    #[arg(
+        long,
+        value_name = "USER:PASS",
+        env = "COVERAGE_HTTP_AUTH",
+        hide_env_values = true
+    )]
+    auth: Option<auth::Credentials>,
+
+    /// Seconds to wait for a clean shutdown after Ctrl+C before forcing the exit; 0 waits
+    /// indefinitely
+    #[arg(long, value_name = "SECS", default_value_t = 2)]
+    shutdown_timeout: u64,
+
+    /// Log every request with its method, path, status and latency
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Only print errors, warnings and the results of each run (same as --log-level warn)
+    #[arg(short, long, conflicts_with = "log_level")]
+    quiet: bool,
+
+    /// Most detailed kind of message to print; debug adds timestamps and the messages of
+    /// the libraries used [default: info]
+    #[arg(long, value_enum, value_name = "LEVEL")]
+    log_level: Option<logging::LogLevel>,
+
+    /// File served for a directory of the report, for generators whose entry page isn't
+    /// index.html
+    #[arg(long, value_name = "NAME", default_value = "index.html")]
+    index_file: String,
+
+    /// Never write a placeholder page, leaving the contents of the report directory
+    /// entirely to the coverage tool
+    #[arg(long, conflicts_with_all = ["placeholder", "refresh"])]
+    no_placeholder: bool,
+
+    /// HTML file to use as the placeholder page shown until the first report is generated
+    #[arg(long, value_name = "FILE")]
+    placeholder: Option<String>,
+
+    /// Icon to serve at /favicon.ico instead of the built-in one
+    #[arg(long, value_name = "FILE")]
+    favicon: Option<String>,
+
+    /// Make the generated placeholder page reload itself every SECONDS until a report
+    /// replaces it
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    refresh: Option<u64>,
+}
+
+/// Modes other than the interactive prompt used without a command
+#[derive(Subcommand, Debug, Clone)]
+enum CliCommand {
+    /// Only serve the report already in the directory, without running coverage
+    Serve,
+    /// Run coverage once, then keep serving the report (same as --once)
+    Run {
+        /// Exit right after the run instead of serving the report
+        #[arg(long)]
+        exit: bool,
+    },
+    /// Re-run coverage whenever a file matching the glob changes, without prompting
+    Watch {
+        #[arg(value_name = "GLOB", default_value = "**/*.py")]
+        glob: String,
+    },
+    /// Print a completion script for the given shell to stdout
+    Completions { shell: clap_complete::Shell },
+}
+
+/// Test runner, and with it the coverage tool, used for a run
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum TestRunner {
+    /// pytest under coverage.py
+    Pytest,
+    /// unittest under coverage.py
+    Unittest,
+    /// cargo llvm-cov, for Rust projects
+    LlvmCov,
+    /// Jest with its built-in coverage, for JavaScript projects
+    Jest,
+}
+
+impl TestRunner {
+    /// Whether the runner goes through coverage.py and so needs a Python interpreter
+    fn uses_python(self) -> bool {
+        matches!(self, TestRunner::Pytest | TestRunner::Unittest)
+    }
+
+    /// Directory the runner writes its HTML report to, used when `--dir` isn't given
+    fn default_dir(self) -> &'static str {
+        match self {
+            TestRunner::Pytest | TestRunner::Unittest => "htmlcov",
+            TestRunner::LlvmCov => "target/llvm-cov/html",
+            TestRunner::Jest => "coverage/lcov-report",
+        }
+    }
+
+    /// Extensions of the files whose changes trigger a run in watch mode, used when
+    /// `--watch-ext` isn't given
+    fn default_watch_extensions(self) -> &'static [&'static str] {
+        match self {
+            TestRunner::Pytest | TestRunner::Unittest => &["py"],
+            TestRunner::LlvmCov => &["rs"],
+            TestRunner::Jest => &["js", "jsx", "ts", "tsx"],
+        }
+    }
+}
+
+/// Whether the given bind address is only reachable from this machine
+fn is_loopback_host(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Host to put in URLs shown to the user, replacing IPv4 loopback and wildcard addresses
+/// with `localhost` and putting IPv6 addresses in brackets. `[::1]` is kept as it is, as
+/// `localhost` may resolve to 127.0.0.1 where nothing is listening.
+fn url_host(host: &str) -> String {
+    match host.parse::<IpAddr>() {
+        Ok(ip) if ip.is_unspecified() => "localhost".to_string(),
+        Ok(IpAddr::V6(ip)) => format!("[{}]", ip),
+        _ if is_loopback_host(host) => "localhost".to_string(),
+        _ => host.to_string(),
+    }
+}
+
+/// URLs under which a server bound to a wildcard address can be reached from other
+/// machines, one per non-loopback interface address. Empty for any other bind address or
+/// when the interfaces can't be listed.
+fn network_urls(host: &str, scheme: &str, port: u16) -> Vec<String> {
+    let Ok(bind_ip) = host.parse::<IpAddr>() else {
+        return Vec::new();
+    };
+    if !bind_ip.is_unspecified() {
+        return Vec::new();
+    }
+    let interfaces = match if_addrs::get_if_addrs() {
+        Ok(interfaces) => interfaces,
+        Err(e) => {
+            warn!("Warning: could not list network interfaces: {}", e);
+            return Vec::new();
+        }
+    };
+
+    interfaces
+        .iter()
+        .filter(|interface| !interface.is_loopback() && !interface.is_link_local())
+        // An IPv4 wildcard only accepts IPv4 connections, an IPv6 one usually both
+        .filter(|interface| bind_ip.is_ipv6() || interface.ip().is_ipv4())
+        .map(|interface| match interface.ip() {
+            IpAddr::V4(ip) => format!("{}://{}:{}/", scheme, ip, port),
+            IpAddr::V6(ip) => format!("{}://[{}]:{}/", scheme, ip, port),
+        })
+        .collect()
+}
+
+/// Open the given URL in the default browser
+fn open_browser(url: &str) -> io::Result<()> {
+    let status = if cfg!(target_os = "windows") {
+        Command::new("cmd")
+            .args(["/C", "start", "", url])
+            .status()?
+    } else if cfg!(target_os = "macos") {
+        Command::new("open").arg(url).status()?
+    } else {
+        Command::new("xdg-open").arg(url).status()?
+    };
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "browser command exited with {}",
+            status
+        )))
+    }
+}
+
+/// Number of consecutive ports tried when the requested one is already in use
+const PORT_FALLBACK_ATTEMPTS: u16 = 20;
+
+/// Wait before the first retry of a bind, doubled for each one after it
+const BIND_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Longest wait between two retries of a bind
+const MAX_BIND_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Bind to the requested port, retrying it up to `retries` times while it is in use in
+/// case it is about to be released, then moving on to the next ones
+async fn bind_listener(host: &str, port: u16, retries: u32) -> io::Result<(TcpListener, u16)> {
+    let mut delay = BIND_RETRY_DELAY;
+    for attempt in 1..=retries {
+        match TcpListener::bind((host, port)) {
+            // Port 0 picks a free one
+            Ok(listener) => {
+                let port = listener.local_addr()?.port();
+                return Ok((listener, port));
+            }
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+                info!(
+                    "Port {} is in use, retrying in {}ms ({} of {})",
+                    port,
+                    delay.as_millis(),
+                    attempt,
+                    retries
+                );
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_BIND_RETRY_DELAY);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    let last_port = port.saturating_add(PORT_FALLBACK_ATTEMPTS - 1);
+    for candidate in port..=last_port {
+        match TcpListener::bind((host, candidate)) {
+            Ok(listener) => {
+                let port = listener.local_addr()?.port();
+                return Ok((listener, port));
+            }
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse && candidate < last_port => {
+                info!(
+                    "Port {} is already in use, trying {}",
+                    candidate,
+                    candidate + 1
+                );
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!("the last candidate port always returns")
+}
+
+/// Bind the server's listeners, on the port `bind_listener` settles on. `localhost` gets
+/// one on each loopback address, since a browser may resolve it to either, unless IPv6
+/// isn't available.
+async fn bind_listeners(
+    host: &str,
+    port: u16,
+    retries: u32,
+) -> io::Result<(Vec<TcpListener>, u16)> {
+    if !host.eq_ignore_ascii_case("localhost") {
+        let (listener, port) = bind_listener(host, port, retries).await?;
+        return Ok((vec![listener], port));
+    }
+
+    let (listener, port) = bind_listener("127.0.0.1", port, retries).await?;
+    let mut listeners = vec![listener];
+    match TcpListener::bind((Ipv6Addr::LOCALHOST, port)) {
+        Ok(listener) => listeners.push(listener),
+        Err(e) => info!(
+            "Serving on 127.0.0.1 only, as [::1]:{} isn't available: {}",
+            port, e
+        ),
+    }
+    Ok((listeners, port))
+}
 
-    let html_dir = html_dir.to_string();
+/// Sockets the server listens on
+enum Listeners {
+    Tcp(Vec<TcpListener>),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixListener),
+}
+
+/// Bind a Unix domain socket at `path`, replacing the socket file a previous instance
+/// left behind if it didn't shut down cleanly, but not one still being served
+#[cfg(unix)]
+fn bind_unix_socket(path: &Path) -> io::Result<Listeners> {
+    use std::os::unix::{fs::FileTypeExt, net::UnixListener, net::UnixStream};
+
+    let is_socket = std_fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket());
+    if is_socket && UnixStream::connect(path).is_err() {
+        std_fs::remove_file(path)?;
+    }
+    UnixListener::bind(path).map(Listeners::Unix)
+}
+
+#[cfg(not(unix))]
+fn bind_unix_socket(_path: &Path) -> io::Result<Listeners> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Unix domain sockets are only supported on Unix",
+    ))
+}
+
+/// Served for paths that don't exist in the report, which happens when a regenerated
+/// report no longer has the page the browser was on
+const NOT_FOUND_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>Page Not Found</title>
+    <style>
+        body { font-family: Arial, sans-serif; margin: 40px; color: #333; }
+        a { color: #3498db; }
+    </style>
+</head>
+<body>
+    <h1>Page not found</h1>
+    <p>This page isn't part of the current coverage report. The report may have changed since the last run.</p>
+    <p><a href="/">Back to the report index</a></p>
+</body>
+</html>"#;
+
+async fn not_found() -> HttpResponse {
+    HttpResponse::NotFound()
+        .content_type(ContentType::html())
+        .body(NOT_FOUND_PAGE)
+}
+
+/// Whether `path`, relative to `root`, is inside `root` once symlinks are followed.
+/// actix-files already refuses `..` segments, percent-encoded or not, so this only
+/// catches links in the report directory that point out of it.
+fn stays_within(root: &Path, path: &Path) -> bool {
+    match (root.canonicalize(), root.join(path).canonicalize()) {
+        (Ok(root), Ok(target)) => target.starts_with(root),
+        // Nothing to escape to; actix-files answers with a 404
+        _ => true,
+    }
+}
+
+/// Service serving the report directory, with `index_file` for directories and, with
+/// `list`, a listing of directories without one
+fn report_files(html_dir: &str, index_file: &str, no_cache: bool, list: bool) -> fs::Files {
+    let root = PathBuf::from(html_dir);
+    let files = fs::Files::new("/", html_dir)
+        .index_file(index_file)
+        .use_etag(!no_cache)
+        .use_last_modified(!no_cache)
+        .path_filter(move |path, _| stays_within(&root, path))
+        .default_handler(web::to(not_found));
+    // The index file is still served where it exists
+    if list {
+        files.show_files_listing()
+    } else {
+        files
+    }
+}
+
+async fn start_http_server(session: Arc<Session>, shutdown: Arc<Shutdown>) -> io::Result<()> {
+    let args = &session.args;
+    let events = session.events.clone();
+    let host = &args.host;
+    let tls_config = args
+        .tls
+        .then(|| tls::self_signed_config(host))
+        .transpose()?;
+
+    // Without a TCP port there is no URL to show or open, the reverse proxy has its own
+    let listeners = if let Some(path) = &args.unix_socket {
+        let listeners = bind_unix_socket(path)?;
+        info!("Starting HTTP server on Unix socket {}", path.display());
+        listeners
+    } else {
+        let (listeners, port) = bind_listeners(host, args.port, args.bind_retries).await?;
+        let scheme = if tls_config.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        let url = format!("{}://{}:{}/", scheme, url_host(host), port);
+        info!(
+            "Starting HTTP server on {}\nNavigate to this URL to view coverage reports",
+            url
+        );
+        *session.url.lock().unwrap() = Some(url.clone());
+        let network_urls = network_urls(host, scheme, port);
+        if !network_urls.is_empty() {
+            info!("On your network:");
+            for network_url in network_urls {
+                info!("  {}", network_url);
+            }
+        }
+        if !is_loopback_host(host) {
+            warn!(
+                "Warning: binding to {} makes the coverage report reachable by anyone on the network",
+                host
+            );
+        }
+        if args.open {
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = open_browser(&url) {
+                    warn!("Warning: could not open browser: {}", e);
+                }
+            });
+        }
+        Listeners::Tcp(listeners)
+    };
+    if let Some((_, fingerprint)) = &tls_config {
+        info!(
+            "Using a self-signed certificate with SHA-256 fingerprint {}",
+            fingerprint
+        );
+    }
+
+    let html_dir = args.dir.clone();
+    let index_file = args.index_file.clone();
+    let unix_socket = args.unix_socket.clone();
+    let live_reload = !args.no_live_reload;
+    let compress = !args.no_compress;
+    let verbose = args.verbose;
+    let list = args.list;
+    let no_cache = args.no_cache;
+    let allow_run = args.allow_run;
+    let server_events = events.clone();
+    let console = session.console.clone();
+    let report_dir = web::Data::new(api::ReportDir(PathBuf::from(&html_dir)));
+    let favicon = web::Data::new(favicon::Favicon::load(args.favicon.as_deref())?);
+    let session = web::Data::from(session.clone());
+    let shutdown_data = web::Data::from(shutdown.clone());
     let server = HttpServer::new(move || {
-        App::new().service(fs::Files::new("/", &html_dir).index_file("index.html"))
-    })
-    .bind("127.0.0.1:8080")?
-    .run();
+        App::new()
+            .app_data(web::Data::new(server_events.clone()))
+            .app_data(web::Data::new(console.clone()))
+            .app_data(report_dir.clone())
+            .app_data(favicon.clone())
+            .app_data(session.clone())
+            .app_data(shutdown_data.clone())
+            .wrap_fn(move |req, srv| {
+                let response = srv.call(req);
+                async move {
+                    let response = response.await?;
+                    if live_reload {
+                        live_reload::inject_script(response).await
+                    } else {
+                        Ok(response.map_into_boxed_body())
+                    }
+                }
+            })
+            // Registered after the script injection so it compresses the final body
+            .wrap(Condition::new(compress, Compress::default()))
+            // Cached pages are always revalidated, which only costs a 304 as long as the
+            // file's ETag and Last-Modified are unchanged
+            .wrap(DefaultHeaders::new().add(CacheControl(vec![if no_cache {
+                CacheDirective::NoStore
+            } else {
+                CacheDirective::NoCache
+            }])))
+            // Outermost, so unauthorized requests never reach the other middleware
+            .wrap(from_fn(auth::require))
+            // Wrapping everything else, so rejected requests are logged too and the
+            // latency covers all the middleware
+            .wrap(Condition::new(verbose, Logger::new("%r %s %Dms")))
+            .route(live_reload::EVENTS_PATH, web::get().to(live_reload::events))
+            .route(console::STREAM_PATH, web::get().to(console::stream))
+            .route("/health", web::get().to(api::health))
+            // Ahead of the report, which has no favicon.ico of its own
+            .route("/favicon.ico", web::get().to(favicon::serve))
+            .configure(assets::routes)
+            .route("/api/coverage", web::get().to(api::coverage))
+            .route("/badge.svg", web::get().to(api::badge))
+            .configure(|config| {
+                if allow_run {
+                    config
+                        .route("/api/run", web::post().to(api::run))
+                        .route("/api/shutdown", web::post().to(api::shutdown));
+                }
+            })
+            .route("/snapshots", web::get().to(snapshot::index))
+            .route("/snapshots/", web::get().to(snapshot::index))
+            .service(report_files(&html_dir, &index_file, no_cache, list))
+    });
+    // Signals are handled in `main`, which shuts the server down along with the rest
+    let server = {
+        let mut server = server.disable_signals();
+        match listeners {
+            Listeners::Tcp(listeners) => {
+                for listener in listeners {
+                    server = match &tls_config {
+                        Some((config, _)) => server.listen_rustls_0_23(listener, config.clone())?,
+                        None => server.listen(listener)?,
+                    };
+                }
+            }
+            #[cfg(unix)]
+            Listeners::Unix(listener) => server = server.listen_uds(listener)?,
+        }
+        server.run()
+    };
 
     let server_handle = server.handle();
 
-    // Monitor task to shut down server when running is false
     tokio::spawn(async move {
-        while running.load(Ordering::SeqCst) {
-            tokio::time::sleep(Duration::from_millis(100).into()).await;
-        }
-        println!("Shutting down HTTP server...");
+        shutdown.requested().await;
+        info!("Shutting down HTTP server...");
+        // Close live reload streams so they don't hold up the graceful stop
+        let _ = events.send(LiveEvent::Shutdown);
         server_handle.stop(true).await;
-        println!("HTTP server shutdown complete");
+        info!("HTTP server shutdown complete");
     });
 
-    server.await
+    let result = server.await;
+    if let Some(path) = &unix_socket
+        && let Err(e) = std_fs::remove_file(path)
+    {
+        warn!("Warning: could not remove {}: {}", path.display(), e);
+    }
+    result
 }
 
-fn run_coverage(python_cmd: &str) -> io::Result<()> {
-    println!("Running coverage tests...");
+/// Outcome of a coverage run whose commands could all be started
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunStatus {
+    Succeeded,
+    /// A command exited unsuccessfully and the remaining ones were skipped
+    Failed {
+        code: Option<i32>,
+    },
+}
 
-    for cmd in python_cmd.split("&&") {
-        let trimmed_cmd = cmd.trim();
-        println!("Executing: {}", trimmed_cmd);
+/// Result of a coverage run whose commands could all be started
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RunOutcome {
+    status: RunStatus,
+    /// Everything the commands that ran wrote to stdout
+    stdout: String,
+    /// How long the commands took, up to the one that failed if any
+    elapsed: Duration,
+}
 
-        let mut parts = trimmed_cmd.split_whitespace();
-        let program = parts.next().unwrap_or("");
-        let args: Vec<&str> = parts.collect();
+/// How a finished coverage run went, as reflected in the exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum RunResult {
+    Succeeded,
+    /// A command of the run failed or couldn't be started
+    Failed,
+    /// The run succeeded but its total missed `--fail-under`
+    BelowThreshold,
+}
 
-        let status = Command::new(program)
-            .args(&args)
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .status()?;
+impl RunResult {
+    fn code(self) -> u8 {
+        match self {
+            RunResult::Succeeded => 0,
+            RunResult::Failed => 1,
+            RunResult::BelowThreshold => 2,
+        }
+    }
+
+    fn exit_code(self) -> ExitCode {
+        ExitCode::from(self.code())
+    }
+}
+
+/// A program and its arguments, run as one step of a coverage run
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CoverageCommand {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CoverageCommand {
+    fn new(program: &str, args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// A program to run without arguments, or to add them to with `with_args`
+    fn program(program: &str) -> Self {
+        Self::new(program, Vec::<String>::new())
+    }
+
+    /// The same command with more arguments appended, for building commands on top of an
+    /// interpreter invocation such as `poetry run python`
+    fn with_args(&self, args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let mut command = self.clone();
+        command.args.extend(args.into_iter().map(Into::into));
+        command
+    }
+}
+
+impl fmt::Display for CoverageCommand {
+    /// Shell-quoted form, suitable for copy-pasting into a terminal
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let words = iter::once(&self.program).chain(&self.args);
+        let quoted = shlex::try_join(words.map(String::as_str)).map_err(|_| fmt::Error)?;
+        f.write_str(&quoted)
+    }
+}
+
+/// How often a running command is checked for completion
+const CHILD_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Wait for the child in `slot` to exit, releasing the lock between checks so the child
+/// can be killed from another thread in the meantime
+fn wait_for_child(slot: &Mutex<Option<Child>>) -> io::Result<ExitStatus> {
+    loop {
+        {
+            let mut child = slot.lock().unwrap();
+            let result = child
+                .as_mut()
+                .expect("the child is only removed here")
+                .try_wait();
+            match result {
+                Ok(None) => {}
+                Ok(Some(status)) => {
+                    *child = None;
+                    return Ok(status);
+                }
+                Err(e) => {
+                    *child = None;
+                    return Err(e);
+                }
+            }
+        }
+        thread::sleep(CHILD_POLL_INTERVAL);
+    }
+}
+
+/// Copy everything read from `reader` to `terminal`, below the status line if there is
+/// one, to the shared log file if there is one, to `capture` if given and line by line
+/// to the consoles. Runs on a separate thread so stdout and stderr can be drained at
+/// the same time.
+fn forward(
+    mut reader: impl Read + Send + 'static,
+    mut terminal: impl Write + Send + 'static,
+    log: Option<Arc<Mutex<std_fs::File>>>,
+    progress: Option<Arc<Progress>>,
+    capture: Option<Arc<Mutex<Vec<u8>>>>,
+    mut console: LineSender,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut buffer = [0; 8192];
+        while let Ok(n) = reader.read(&mut buffer) {
+            if n == 0 {
+                break;
+            }
+            let output = &buffer[..n];
+            let _ = match &progress {
+                Some(progress) => progress.write(&mut terminal, output),
+                None => terminal.write_all(output).and_then(|()| terminal.flush()),
+            };
+            if let Some(log) = &log
+                && let Err(e) = log.lock().unwrap().write_all(output)
+            {
+                error!("Error writing to log file: {}", e);
+            }
+            if let Some(capture) = &capture {
+                capture.lock().unwrap().extend_from_slice(output);
+            }
+            console.write(output);
+        }
+        console.finish();
+    })
+}
+
+/// Environment variables asking common tools for colored output even though they
+/// write to a pipe, for when that pipe ends up on a terminal anyway
+const FORCE_COLOR_ENV: &[(&str, &str)] = &[
+    ("FORCE_COLOR", "1"),
+    ("PY_COLORS", "1"),
+    ("CLICOLOR_FORCE", "1"),
+    ("CARGO_TERM_COLOR", "always"),
+];
+
+/// Run the commands in order, stopping at the first one that fails. The running child
+/// is kept in `current_child` so it can be killed on shutdown. Their output is shown on
+/// the terminal and their stdout is also collected into the outcome, so the test
+/// runner's summary can be read from it. If `log_file` is given, it is overwritten with
+/// the commands and their combined output. With `show_progress`, a spinner and the
+/// elapsed time are shown below the output while each command runs. The commands and
+/// their output are also sent to `console`.
+fn run_coverage(
+    commands: &[CoverageCommand],
+    current_child: &Mutex<Option<Child>>,
+    log_file: Option<&str>,
+    show_progress: bool,
+    console: &broadcast::Sender<ConsoleEvent>,
+) -> io::Result<RunOutcome> {
+    info!("Running coverage tests...");
+    let log = log_file
+        .map(|path| std_fs::File::create(path).map(|file| Arc::new(Mutex::new(file))))
+        .transpose()?;
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let start = Instant::now();
+    let outcome = |status| RunOutcome {
+        status,
+        stdout: String::from_utf8_lossy(&stdout.lock().unwrap()).into_owned(),
+        elapsed: start.elapsed(),
+    };
+
+    for (i, command) in commands.iter().enumerate() {
+        info!("Executing: {}", command);
+        let _ = console.send(ConsoleEvent::Command(command.to_string()));
+
+        let mut process = Command::new(&command.program);
+        process.args(&command.args);
+        process.stdout(Stdio::piped()).stderr(Stdio::piped());
+        // Tools no longer see a terminal, so ask for the colors they would have used
+        if log.is_none() && io::stdout().is_terminal() {
+            process.envs(FORCE_COLOR_ENV.iter().copied());
+        }
+        let mut child = process.spawn()?;
+
+        if let Some(log) = &log {
+            writeln!(log.lock().unwrap(), "$ {}", command)?;
+        }
+        let progress =
+            show_progress.then(|| Progress::start(format!("Step {} of {}", i + 1, commands.len())));
+        let mut readers = Vec::new();
+        if let Some(child_stdout) = child.stdout.take() {
+            readers.push(forward(
+                child_stdout,
+                io::stdout(),
+                log.clone(),
+                progress.clone(),
+                Some(stdout.clone()),
+                LineSender::new(console.clone(), console::Stream::Stdout),
+            ));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(forward(
+                stderr,
+                io::stderr(),
+                log.clone(),
+                progress.clone(),
+                None,
+                LineSender::new(console.clone(), console::Stream::Stderr),
+            ));
+        }
+        *current_child.lock().unwrap() = Some(child);
+        let status = wait_for_child(current_child);
+        for reader in readers {
+            let _ = reader.join();
+        }
+        if let Some(progress) = progress {
+            progress.finish();
+        }
+        let status = status?;
 
         if !status.success() {
-            println!("Command failed with exit code: {:?}", status.code());
-            return Ok(());
+            let outcome = outcome(RunStatus::Failed {
+                code: status.code(),
+            });
+            error!(
+                "Command failed with exit code {:?} after {:.1}s",
+                status.code(),
+                outcome.elapsed.as_secs_f64()
+            );
+            return Ok(outcome);
         }
     }
 
-    println!("Coverage tests completed successfully!");
-    Ok(())
+    let outcome = outcome(RunStatus::Succeeded);
+    info!(
+        "Coverage tests completed successfully in {:.1}s!",
+        outcome.elapsed.as_secs_f64()
+    );
+    Ok(outcome)
 }
 
-/// Find and return the path to the Python interpreter
-fn get_python_path() -> io::Result<String> {
+/// Find and return the path to the given Python interpreter, if it is on the PATH.
+/// `where` on Windows lists every match, so the first one that exists on disk is used.
+fn get_python_path(python: &str) -> io::Result<Option<String>> {
     let cmd = if cfg!(target_os = "windows") {
         "where"
     } else {
         "which"
     };
-    let output = Command::new(cmd).arg("python").output()?;
+    let output = Command::new(cmd).arg(python).output()?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let candidates: Vec<&str> = stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    Ok(candidates
+        .iter()
+        .find(|candidate| Path::new(candidate).exists())
+        .or(candidates.first())
+        .map(|path| path.to_string()))
+}
+
+/// Lock files of the environment managers whose environment is preferred, in order,
+/// with the command running a program in that environment
+const ENV_MANAGERS: &[(&str, &str)] = &[("poetry.lock", "poetry"), ("Pipfile.lock", "pipenv")];
 
-    Ok(if output.status.success() {
-        String::from_utf8_lossy(&output.stdout).trim().to_string()
+/// Virtualenv directories whose interpreter is preferred, in order
+const VENV_DIRS: &[&str] = &[".venv", "venv"];
+
+/// Interpreter of the first virtualenv found in the working directory, if any
+fn find_venv_python() -> Option<PathBuf> {
+    let interpreter = if cfg!(target_os = "windows") {
+        Path::new("Scripts").join("python.exe")
     } else {
-        "Python path not found".to_string()
-    })
+        Path::new("bin").join("python")
+    };
+    VENV_DIRS
+        .iter()
+        .map(|dir| Path::new(dir).join(&interpreter))
+        .find(|path| path.is_file())
 }
 
-/// Create directory and index.html if they don't exist
-fn setup_html_dir(dir_path: &str) -> io::Result<()> {
+/// Python interpreter picked for the session, resolved once at startup
+struct Python {
+    /// How the interpreter is invoked for every run
+    command: CoverageCommand,
+    /// Where it was found on the PATH, if it was looked up and found
+    path: Option<String>,
+}
+
+/// Pick the Python interpreter to use: the `--python` override if given, used as is
+/// without looking it up, then with `use_env_manager` `python` run by Poetry or Pipenv
+/// if the working directory has their lock file, then with `use_venv` the interpreter
+/// of a virtualenv in the working directory, otherwise `python`, falling back to
+/// `python3` when only that one is on the PATH
+fn resolve_python(requested: Option<&str>, use_env_manager: bool, use_venv: bool) -> Python {
+    if let Some(python) = requested {
+        return Python {
+            command: CoverageCommand::program(python),
+            path: None,
+        };
+    }
+    if use_env_manager
+        && let Some((lock_file, manager)) = ENV_MANAGERS
+            .iter()
+            .find(|(lock_file, _)| Path::new(lock_file).is_file())
+    {
+        info!(
+            "Running Python with `{} run` since {} was found (pass --no-poetry to skip it)",
+            manager, lock_file
+        );
+        return Python {
+            command: CoverageCommand::new(manager, ["run", "python"]),
+            path: None,
+        };
+    }
+    if use_venv && let Some(venv_python) = find_venv_python() {
+        info!(
+            "Using the virtualenv interpreter {} (pass --no-venv to use the one on the PATH)",
+            venv_python.display()
+        );
+        let command = venv_python.to_string_lossy().into_owned();
+        // Not canonicalized, which would follow the symlink out of the virtualenv
+        let path = absolute_path(&venv_python)
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| command.clone());
+        return Python {
+            command: CoverageCommand::program(&command),
+            path: Some(path),
+        };
+    }
+
+    for candidate in ["python", "python3"] {
+        match get_python_path(candidate) {
+            Ok(Some(path)) => {
+                return Python {
+                    command: CoverageCommand::program(candidate),
+                    path: Some(path),
+                };
+            }
+            Ok(None) => {}
+            Err(e) => error!("Error looking up Python interpreter {}: {}", candidate, e),
+        }
+    }
+    Python {
+        command: CoverageCommand::program("python"),
+        path: None,
+    }
+}
+
+/// Appended to the placeholder index.html so it can be told apart from a real report
+const PLACEHOLDER_MARKER: &str = "<!-- coverage-http placeholder -->";
+
+/// Whether the file at `path` is missing or is a placeholder written by this tool, so it
+/// can be (re)written without losing a real report
+fn is_missing_or_placeholder(path: &Path) -> io::Result<bool> {
+    match std_fs::read_to_string(path) {
+        Ok(contents) => Ok(contents.contains(PLACEHOLDER_MARKER)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(true),
+        // Not UTF-8, so not ours
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Absolute form of `path`, relative to the working directory, with `.` and `..`
+/// resolved without touching the file system, so it works for paths that don't exist yet
+fn absolute_path(path: &Path) -> io::Result<PathBuf> {
+    let mut absolute = PathBuf::new();
+    for component in std::env::current_dir()?.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                absolute.pop();
+            }
+            component => absolute.push(component),
+        }
+    }
+    Ok(absolute)
+}
+
+/// Ask a yes/no question on the terminal, defaulting to no
+fn confirm(question: &str) -> io::Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Check the report directory before anything is written to it. An empty path is
+/// rejected, and a directory that would have to be created outside the working
+/// directory is only created once the user confirms it at the terminal.
+fn validate_html_dir(dir_path: &str) -> io::Result<()> {
+    if dir_path.trim().is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the report directory must not be empty",
+        ));
+    }
+
+    let path = Path::new(dir_path);
+    let absolute = absolute_path(path)?;
+    if path.exists() || absolute.starts_with(std::env::current_dir()?) {
+        return Ok(());
+    }
+    let question = format!(
+        "{} is outside the current directory and doesn't exist. Create it?",
+        absolute.display()
+    );
+    if !io::stdin().is_terminal() || !confirm(&question)? {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "not creating {} outside the current directory; create it first to use it",
+                absolute.display()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Whether an error means the file system refused a write, rather than something going
+/// wrong while writing
+fn is_not_writable(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem
+    )
+}
+
+/// Create the directory and, unless `write_placeholder` is false, its `index_file` if
+/// they don't exist. The placeholder page is read from `placeholder` if given, and
+/// reloads itself every `refresh` seconds if given. A placeholder left by a previous
+/// session is rewritten so the current options apply, but a real report's index file is
+/// never touched. If the directory exists but isn't writable, a warning is printed and
+/// it is served as is.
+fn setup_html_dir(
+    dir_path: &str,
+    index_file: &str,
+    write_placeholder: bool,
+    placeholder: Option<&str>,
+    refresh: Option<u64>,
+) -> io::Result<()> {
     // Create directory if needed
     if !Path::new(dir_path).exists() {
-        println!("Creating directory: {}", dir_path);
-        std_fs::create_dir_all(dir_path)?;
+        info!("Creating directory: {}", dir_path);
+        std_fs::create_dir_all(dir_path)
+            .map_err(|e| io::Error::new(e.kind(), format!("can't create the directory: {}", e)))?;
     }
 
-    // Create index.html if needed
-    let index_path = Path::new(dir_path).join("index.html");
-    if !index_path.exists() {
-        println!("Creating empty index.html file in: {}", dir_path);
-        let html_content = r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Coverage Report</title>
-    <style>
-        body {
-            font-family: Arial, sans-serif;
-            line-height: 1.6;
-            margin: 0;
-            padding: 20px;
-            color: #333;
-        }
-        .container {
-            max-width: 800px;
-            margin: 0 auto;
-            background-color: #f9f9f9;
-            padding: 20px;
-            border-radius: 5px;
-            box-shadow: 0 2px 5px rgba(0,0,0,0.1);
-        }
-        h1 {
-            color: #2c3e50;
-            border-bottom: 1px solid #ddd;
-            padding-bottom: 10px;
-        }
-        .message {
-            background-color: #e7f2fa;
-            border-left: 4px solid #3498db;
-            padding: 15px;
-            margin: 20px 0;
-        }
-        .hint {
-            background-color: #fef5e7;
-            border-left: 4px solid #f39c12;
-            padding: 15px;
-            margin: 20px 0;
+    if !write_placeholder {
+        return Ok(());
+    }
+
+    // Create the index file if needed
+    let index_path = Path::new(dir_path).join(index_file);
+    if is_missing_or_placeholder(&index_path)? {
+        if index_path.exists() {
+            info!("Updating placeholder {} file in: {}", index_file, dir_path);
+        } else {
+            info!("Creating empty {} file in: {}", index_file, dir_path);
+        }
+        let html_content = match placeholder {
+            Some(path) => std_fs::read_to_string(path).map_err(|e| {
+                io::Error::new(e.kind(), format!("reading placeholder {}: {}", path, e))
+            })?,
+            None => assets::text(assets::PLACEHOLDER_PAGE),
+        };
+        let html_content = match refresh {
+            Some(seconds) => html_content.replacen(
+                "</head>",
+                &format!(
+                    "    <meta http-equiv=\"refresh\" content=\"{}\">\n</head>",
+                    seconds
+                ),
+                1,
+            ),
+            None => html_content,
+        };
+        let written = std_fs::write(
+            &index_path,
+            format!("{}\n{}\n", html_content, PLACEHOLDER_MARKER),
+        );
+        // An existing report can still be served from a read-only directory
+        match written {
+            Err(e) if is_not_writable(&e) => warn!(
+                "Warning: {} isn't writable ({}), so only the report already in it is \
+                 served and runs can't update it; make it writable (e.g. `chmod u+w {}`) \
+                 or pick another directory with --dir",
+                dir_path, e, dir_path
+            ),
+            written => written?,
         }
-    </style>
-</head>
-<body>
-    <div class="container">
-        <h1>Coverage Report Placeholder</h1>
-        <div class="message">
-            <p>No coverage reports have been generated yet.</p>
-            <p>Press Enter in the terminal to run the coverage tests.</p>
-        </div>
-        <div class="hint">
-            <p>After the coverage tests complete successfully, refresh this page to see the actual coverage report.</p>
-        </div>
-    </div>
-</body>
-</html>"#;
-        std_fs::write(&index_path, html_content)?;
     }
 
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> io::Result<()> {
-    // Print Python interpreter path
-    if let Ok(path) = get_python_path() {
-        println!("Python interpreter path: {}", path);
+/// Split a test path as entered at the prompt into separate paths, on whitespace or
+/// commas, with quotes keeping paths that contain spaces together
+fn split_test_paths(test_path: &str) -> Vec<String> {
+    let words = shlex::split(test_path)
+        .unwrap_or_else(|| test_path.split_whitespace().map(String::from).collect());
+    let paths: Vec<String> = words
+        .iter()
+        .flat_map(|word| word.split(','))
+        .filter(|path| !path.is_empty())
+        .map(String::from)
+        .collect();
+    if paths.is_empty() {
+        vec![".".to_string()]
+    } else {
+        paths
     }
+}
 
-    // The directory containing the HTML coverage reports
-    let html_dir = "htmlcov";
-    setup_html_dir(html_dir)?;
-
-    // Control flag and test path setup
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
-    let mut current_test_path = ".".to_string();
-
-    // Set up ctrl+c handler
-    ctrlc::set_handler(move || {
-        println!("Received Ctrl+C, shutting down...");
-        r.store(false, Ordering::SeqCst);
-
-        // Force exit after timeout
-        thread::spawn(|| {
-            thread::sleep(Duration::from_secs(2));
-            println!("Forcing exit...");
-            process::exit(0);
-        });
-    })
-    .expect("Error setting Ctrl+C handler");
+/// State shared by everything that can trigger a coverage run
+struct Session {
+    args: Args,
+    /// Coverage tool selected by `--runner`
+    runner: Box<dyn CoverageRunner>,
+    events: broadcast::Sender<LiveEvent>,
+    /// Output of the runs, for the pages following it
+    console: broadcast::Sender<ConsoleEvent>,
+    test_path: Mutex<String>,
+    /// How the last run went, if there was one
+    last_result: Mutex<Option<RunResult>>,
+    /// Total coverage percentage reported by the last successful run
+    last_total: Mutex<Option<f64>>,
+    /// Commit checked out during the run that `last_total` comes from, if in a git
+    /// repository
+    last_commit: Mutex<Option<String>>,
+    /// When the last run finished
+    last_run_at: Mutex<Option<DateTime<Local>>>,
+    /// Where the report is served, once the server is listening
+    url: Mutex<Option<String>>,
+    /// Held for the duration of a run. A run started while it is held is refused rather
+    /// than queued, so the coverage data file is only ever written by one run.
+    run_lock: Mutex<()>,
+    /// Command of the current run that is still executing, if any
+    current_child: Mutex<Option<Child>>,
+}
+
+impl Session {
+    fn new(
+        args: Args,
+        runner: Box<dyn CoverageRunner>,
+        events: broadcast::Sender<LiveEvent>,
+    ) -> Self {
+        Self {
+            test_path: Mutex::new(args.test_path.clone().unwrap_or_else(|| ".".to_string())),
+            runner,
+            args,
+            events,
+            console: broadcast::channel(console::CAPACITY).0,
+            last_result: Mutex::new(None),
+            last_total: Mutex::new(None),
+            last_commit: Mutex::new(None),
+            last_run_at: Mutex::new(None),
+            url: Mutex::new(None),
+            run_lock: Mutex::new(()),
+            current_child: Mutex::new(None),
+        }
+    }
+
+    fn test_path(&self) -> String {
+        self.test_path.lock().unwrap().clone()
+    }
+
+    /// The individual paths in the current test path
+    fn test_paths(&self) -> Vec<String> {
+        split_test_paths(&self.test_path())
+    }
+
+    fn set_test_path(&self, path: &str) {
+        *self.test_path.lock().unwrap() = path.to_string();
+        if !self.args.no_persist
+            && let Err(e) = state::save_test_path(path)
+        {
+            error!("Error saving test path to {}: {}", state::STATE_FILE, e);
+        }
+    }
+
+    fn last_total(&self) -> Option<f64> {
+        *self.last_total.lock().unwrap()
+    }
+
+    fn last_commit(&self) -> Option<String> {
+        self.last_commit.lock().unwrap().clone()
+    }
+
+    fn last_run_at(&self) -> Option<DateTime<Local>> {
+        *self.last_run_at.lock().unwrap()
+    }
+
+    fn last_result(&self) -> Option<RunResult> {
+        *self.last_result.lock().unwrap()
+    }
+
+    fn last_run_failed(&self) -> bool {
+        self.last_result()
+            .is_some_and(|result| result != RunResult::Succeeded)
+    }
+
+    fn url(&self) -> Option<String> {
+        self.url.lock().unwrap().clone()
+    }
+
+    /// Commands making up a coverage run for the current test path
+    fn coverage_commands(&self) -> Vec<CoverageCommand> {
+        self.runner.commands(&self.test_paths())
+    }
+
+    /// Run coverage with the current test path and record the outcome, which is
+    /// returned unless no run happened
+    fn run(&self) -> Option<RunResult> {
+        self.run_commands(&self.coverage_commands())
+    }
+
+    /// Run only the tests that failed in the last run, adding their coverage to that
+    /// run's data, and record the outcome
+    fn retry(&self) {
+        let Some(commands) = self.runner.rerun_failed_commands(&self.test_paths()) else {
+            println!(
+                "Only the pytest runner, without --cmd-template or configured runs, can \
+                 re-run failed tests"
+            );
+            return;
+        };
+        match self.last_result() {
+            Some(RunResult::Failed) => {
+                self.run_commands(&commands);
+            }
+            // Every test passed, so `--last-failed` would have nothing to pick
+            Some(RunResult::BelowThreshold) => println!(
+                "The last run only fell short of --fail-under, so there are no failed tests to \
+                 re-run"
+            ),
+            _ => println!("The last run didn't fail, so there is nothing to re-run"),
+        }
+    }
+
+    /// Run the commands of a coverage run and record the outcome, returning it. With
+    /// `--dry-run`, only print the commands that would be run, and return `None` like
+    /// when another run is in progress.
+    fn run_commands(&self, commands: &[CoverageCommand]) -> Option<RunResult> {
+        if self.args.dry_run {
+            for command in commands {
+                println!("Would execute: {}", command);
+            }
+            return None;
+        }
+
+        let _guard = self.lock_run()?;
+        Some(self.run_locked(commands))
+    }
+
+    /// Run coverage like `run`, after switching to `test_path`. The test path is only
+    /// changed once the run is sure to happen, so a run refused because another one is
+    /// in progress, or by --dry-run, leaves it as it was.
+    fn run_with_test_path(&self, test_path: &str) -> Option<RunResult> {
+        if self.args.dry_run {
+            return self.run_commands(&self.runner.commands(&split_test_paths(test_path)));
+        }
+
+        let _guard = self.lock_run()?;
+        self.set_test_path(test_path);
+        info!("Test path updated to: {}", test_path);
+        Some(self.run_locked(&self.coverage_commands()))
+    }
+
+    /// Take the lock held for the duration of a run, or say why not if another run has it
+    fn lock_run(&self) -> Option<MutexGuard<'_, ()>> {
+        match self.run_lock.try_lock() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::WouldBlock) => {
+                warn!("A run is already in progress");
+                None
+            }
+            Err(TryLockError::Poisoned(e)) => Some(e.into_inner()),
+        }
+    }
+
+    /// Run `commands` and record the outcome, with the run lock held
+    fn run_locked(&self, commands: &[CoverageCommand]) -> RunResult {
+        let report_dir = Path::new(&self.args.dir);
+        let previous_report = report::load_json(report_dir).ok();
+        let previous_total = history::load(report_dir)
+            .ok()
+            .and_then(|entries| entries.last().map(|entry| entry.total));
+        // Taken before the run, since the tests ran against this commit
+        let commit = git::short_head();
+        // Groups the messages of the run when they are shown with their context
+        let _span = info_span!("run", commit = commit.as_deref()).entered();
+        let outcome = run_coverage(
+            commands,
+            &self.current_child,
+            self.args.log_file.as_deref(),
+            !self.args.no_progress && io::stdout().is_terminal(),
+            &self.console,
+        );
+        let tests = outcome
+            .as_ref()
+            .ok()
+            .and_then(|outcome| self.runner.parse_test_counts(&outcome.stdout));
+        if let Some(tests) = &tests {
+            println!("Tests: {}", tests);
+        }
+        let elapsed = outcome.as_ref().ok().map(|outcome| outcome.elapsed);
+        let (result, total) = match outcome.map(|outcome| (outcome.status, outcome.elapsed)) {
+            Ok((RunStatus::Succeeded, elapsed)) => {
+                let total =
+                    self.summarize(previous_report, previous_total, commit.clone(), elapsed);
+
+                // Nobody listening just means no page is open
+                let _ = self.events.send(LiveEvent::Reload);
+                if self.below_threshold(total) {
+                    (RunResult::BelowThreshold, total)
+                } else {
+                    (RunResult::Succeeded, total)
+                }
+            }
+            Ok((RunStatus::Failed { .. }, _)) => {
+                error!("Coverage run failed; the report may be stale or incomplete");
+                (RunResult::Failed, None)
+            }
+            Err(e) => {
+                error!("Error running coverage: {}", e);
+                (RunResult::Failed, None)
+            }
+        };
+        if self.args.notify {
+            notification::run_finished(
+                result != RunResult::Succeeded,
+                total,
+                previous_total,
+                tests.clone(),
+            );
+        }
+        if let Some(path) = &self.args.summary_file {
+            let summary = run_summary::RunSummary {
+                result,
+                timestamp: Utc::now(),
+                duration: elapsed.map(|elapsed| elapsed.as_secs_f64()),
+                commit,
+                total,
+                tests,
+                files: if result == RunResult::Failed {
+                    Vec::new()
+                } else {
+                    self.file_summaries()
+                },
+            };
+            if let Err(e) = run_summary::write(path, &summary) {
+                error!("Error writing run summary to {}: {}", path.display(), e);
+            }
+        }
+        let _ = self.console.send(ConsoleEvent::Finished(result));
+        *self.last_result.lock().unwrap() = Some(result);
+        *self.last_run_at.lock().unwrap() = Some(Local::now());
+        result
+    }
+
+    /// Print and record the results of a successful run, returning its total coverage.
+    /// `previous_report` and `previous_total` are the JSON report and the last recorded
+    /// total as they were before the run, `commit` the commit the run tested and `elapsed`
+    /// how long the run took.
+    fn summarize(
+        &self,
+        previous_report: Option<JsonReport>,
+        previous_total: Option<f64>,
+        commit: Option<String>,
+        elapsed: Duration,
+    ) -> Option<f64> {
+        let report_dir = Path::new(&self.args.dir);
+
+        let total = match self.runner.parse_total() {
+            Ok(Some(total)) => {
+                match previous_total {
+                    Some(previous) => println!(
+                        "Total coverage: {:.1}% ({})",
+                        total,
+                        report::format_delta(total - previous)
+                    ),
+                    None => println!("Total coverage: {:.1}%", total),
+                }
+                Some(total)
+            }
+            Ok(None) => {
+                warn!("Could not find the total in the coverage report");
+                None
+            }
+            Err(e) => {
+                error!("Error reading total coverage: {}", e);
+                None
+            }
+        };
+
+        let report = report::load_json(report_dir).ok();
+        if let Some(report) = &report
+            && let Some(percent) = report.totals.branch_percent()
+        {
+            println!(
+                "Branch coverage: {:.1}% ({} of {} branches)",
+                percent,
+                report.totals.covered_branches.unwrap_or_default(),
+                report.totals.num_branches.unwrap_or_default()
+            );
+        }
+        if self.args.worst > 0 {
+            // Without the JSON report, such as with a command template that skips it
+            let files = match &report {
+                Some(report) => report
+                    .files
+                    .iter()
+                    .map(|(path, file)| (path.clone(), file.summary.percent_covered))
+                    .collect(),
+                None => self.runner.file_coverage().unwrap_or_else(|e| {
+                    error!("Error reading the coverage of each file: {}", e);
+                    Vec::new()
+                }),
+            };
+            report::print_worst(&files, self.args.worst);
+        }
+        if let Some(previous_report) = &previous_report
+            && let Some(report) = &report
+        {
+            report::print_regressions(previous_report, report);
+        }
+        if self.args.github
+            && let Some(report) = &report
+        {
+            report::print_github_annotations(previous_report.as_ref(), report);
+        }
 
-    // Start HTTP server in a separate thread
-    let server_running = running.clone();
-    let server_thread = thread::spawn(move || {
-        tokio::runtime::Runtime::new().unwrap().block_on(async {
-            if let Err(e) = start_http_server(html_dir, server_running).await {
-                eprintln!("HTTP server error: {}", e);
+        *self.last_total.lock().unwrap() = total;
+        *self.last_commit.lock().unwrap() = commit.clone();
+        if let Some(total) = total {
+            let entry = history::Entry {
+                timestamp: Utc::now(),
+                total,
+                commit: commit.clone(),
+                duration: Some(elapsed.as_secs_f64()),
+            };
+            if let Err(e) = history::append(report_dir, &entry, self.args.max_history as usize) {
+                error!("Error recording coverage history: {}", e);
             }
+        }
+
+        if let Some(keep) = self.args.snapshots {
+            match snapshot::take(report_dir, keep as usize, total, commit) {
+                Ok(name) => match self.url() {
+                    Some(url) => info!(
+                        "Saved snapshot: {}{}/{}/",
+                        url,
+                        snapshot::SNAPSHOTS_DIR,
+                        name
+                    ),
+                    None => info!("Saved snapshot: {}", name),
+                },
+                Err(e) => error!("Error saving report snapshot: {}", e),
+            }
+        }
+
+        total
+    }
+
+    /// Coverage of each file in the last run, from the `coverage json` report or else
+    /// the runner's text report
+    fn file_summaries(&self) -> Vec<run_summary::FileSummary> {
+        use run_summary::{FileStats, FileSummary};
+
+        if let Ok(report) = report::load_json(Path::new(&self.args.dir)) {
+            return report
+                .files
+                .into_iter()
+                .map(|(path, file)| FileSummary {
+                    path,
+                    stats: FileStats::Counts(file.summary),
+                })
+                .collect();
+        }
+        match self.runner.file_coverage() {
+            Ok(files) => files
+                .into_iter()
+                .map(|(path, percent_covered)| FileSummary {
+                    path,
+                    stats: FileStats::Percent { percent_covered },
+                })
+                .collect(),
+            Err(e) => {
+                error!("Error reading the coverage of each file: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Kill the command of the current run, if one is executing
+    fn kill_running_command(&self) {
+        if let Some(child) = self.current_child.lock().unwrap().as_mut() {
+            info!("Stopping running command...");
+            if let Err(e) = child.kill() {
+                error!("Error stopping running command: {}", e);
+            }
+        }
+    }
+
+    /// Whether the total misses the `--fail-under` threshold, printing why if so
+    fn below_threshold(&self, total: Option<f64>) -> bool {
+        let Some(threshold) = self.args.fail_under else {
+            return false;
+        };
+
+        match total {
+            Some(total) if total >= threshold => false,
+            Some(total) => {
+                error!(
+                    "Coverage failure: total of {:.1}% is less than fail-under={:.1}%",
+                    total, threshold
+                );
+                true
+            }
+            None => {
+                error!(
+                    "Coverage failure: total is unknown, so fail-under={:.1}% can't be checked",
+                    threshold
+                );
+                true
+            }
+        }
+    }
+}
+
+/// Ask everything to stop and kill the command of the current run. With
+/// `--shutdown-timeout`, the process exits anyway once that has passed.
+fn shut_down(session: &Arc<Session>, shutdown: &Shutdown) {
+    shutdown.trigger();
+    session.kill_running_command();
+
+    // Force exit if the graceful shutdown takes too long
+    let timeout = session.args.shutdown_timeout;
+    if timeout > 0 {
+        let session = session.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_secs(timeout));
+            warn!(
+                "Graceful shutdown took longer than {}s, forcing exit...",
+                timeout
+            );
+            // With the same exit code as a graceful stop
+            process::exit(session.last_result().map_or(0, RunResult::code).into());
         });
-    });
+    }
+}
 
-    println!("Coverage HTTP server started!");
-    println!("Press Enter to run coverage tests with the current test path, or enter a new path");
-    println!("Current test path: {}", current_test_path);
+/// Wait for Ctrl+C or, on Unix, SIGTERM, returning which one arrived
+async fn shutdown_signal() -> io::Result<&'static str> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
 
-    // Main input loop
-    while running.load(Ordering::SeqCst) {
-        print!("> ");
-        io::stdout().flush()?;
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.map(|()| "Ctrl+C"),
+            _ = terminate.recv() => Ok("SIGTERM"),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await.map(|()| "Ctrl+C")
+    }
+}
 
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() || input.trim().to_lowercase() == "exit" {
-            break;
+#[tokio::main]
+async fn main() -> io::Result<ExitCode> {
+    // Command-line flags take precedence over the config file
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // Apart from printing completions, the commands stand for the flags selecting the mode
+    match args.command.clone() {
+        Some(CliCommand::Completions { shell }) => {
+            let mut command = Args::command();
+            let name = command.get_name().to_string();
+            // Buffered so a closed pipe is reported as an error rather than a panic
+            let mut script = Vec::new();
+            clap_complete::generate(shell, &mut command, name, &mut script);
+            io::stdout().write_all(&script)?;
+            return Ok(ExitCode::SUCCESS);
         }
+        Some(CliCommand::Serve) => {
+            args.once = false;
+            args.watch = None;
+        }
+        Some(CliCommand::Run { exit }) => {
+            if exit && args.serve_after {
+                Args::command()
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        "the argument '--exit' cannot be used with '--serve-after'",
+                    )
+                    .exit();
+            }
+            args.once = true;
+            args.exit |= exit;
+        }
+        Some(CliCommand::Watch { glob }) => args.watch = Some(glob),
+        None => args.once |= args.serve_after,
+    }
+    let log_level = args.log_level.unwrap_or(if args.quiet {
+        logging::LogLevel::Warn
+    } else {
+        logging::LogLevel::Info
+    });
+    logging::init(log_level, args.verbose);
 
-        // Update test path if input not empty
-        let trimmed_input = input.trim();
-        if !trimmed_input.is_empty() && trimmed_input.to_lowercase() != "exit" {
-            current_test_path = trimmed_input.to_string();
-            println!("Test path updated to: {}", current_test_path);
+    let serve_only = matches!(args.command, Some(CliCommand::Serve));
+    let interactive = args.command.is_none() && !args.once;
+    match Config::load() {
+        Ok(config) => config.unwrap_or_default().apply(&mut args, &matches),
+        Err(e) => {
+            error!("Error loading config: {}", e);
+            return Ok(ExitCode::FAILURE);
         }
+    }
 
-        // Run coverage with current test path
-        let python_cmd = format!(
-            "python -m coverage run -m pytest {} && python -m coverage html",
-            current_test_path
+    // IPv6 addresses may be given in brackets, as in URLs
+    if let Some(ip) = args
+        .host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+    {
+        args.host = ip.to_string();
+    }
+
+    // Like the report directory, this depends on the runner the config file may set. A
+    // glob spelling out an extension, like `templates/**/*.html`, needs no other filter.
+    let glob_extension = args.watch.as_deref().and_then(watch::glob_extension);
+    if args.watch_ext.is_empty() {
+        if glob_extension.is_none() {
+            args.watch_ext = args
+                .runner
+                .default_watch_extensions()
+                .iter()
+                .map(|extension| extension.to_string())
+                .collect();
+        }
+    } else if let Some(extension) = glob_extension
+        && !args.watch_ext.iter().any(|allowed| {
+            allowed
+                .trim()
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(extension)
+        })
+    {
+        warn!(
+            "Warning: --watch only matches .{} files, which --watch-ext {} leaves out, so no \
+             change will trigger a run",
+            extension,
+            args.watch_ext.join(",")
         );
+    }
+
+    // Fall back to the test path of the previous session, then to the test paths the
+    // project declares for pytest
+    if args.test_path.is_none()
+        && !args.no_persist
+        && let Some(path) = state::load_test_path()
+    {
+        info!("Using test path from the previous session: {}", path);
+        args.test_path = Some(path);
+    }
+    if args.test_path.is_none()
+        && args.runner.uses_python()
+        && let Some((file, paths)) = project::pytest_testpaths()
+    {
+        let test_path =
+            shlex::try_join(paths.iter().map(String::as_str)).unwrap_or_else(|_| paths.join(" "));
+        info!("Using test path from {}: {}", file, test_path);
+        args.test_path = Some(test_path);
+    }
+
+    // Resolve the interpreter once; every run reuses it. Runners that don't go through
+    // coverage.py only need it for a template that runs it, so it isn't looked up or
+    // mentioned otherwise.
+    let uses_python = args.runner.uses_python()
+        || args
+            .cmd_template
+            .as_ref()
+            .is_some_and(runner::CommandTemplate::uses_python);
+    let python = if uses_python {
+        resolve_python(args.python.as_deref(), !args.no_poetry, !args.no_venv)
+    } else {
+        Python {
+            command: CoverageCommand::program(args.python.as_deref().unwrap_or("python")),
+            path: None,
+        }
+    };
+    if uses_python {
+        match &python.path {
+            Some(path) => info!("Python interpreter path: {}", path),
+            // Given with --python or run by Poetry or Pipenv, so not looked up
+            None if args.python.is_some() || !python.command.args.is_empty() => {
+                info!("Python interpreter: {}", python.command)
+            }
+            None => warn!(
+                "Neither python nor python3 was found on the PATH; install Python or pass --python"
+            ),
+        }
+    }
+
+    let runner = runner::from_args(&python.command, &args);
+    // Nothing is run when only serving, so the tools don't matter
+    if !serve_only && !runner.verify_tools() && args.strict {
+        error!("Exiting because required tools are missing (--strict)");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    // The directory containing the HTML coverage reports
+    let setup = validate_html_dir(&args.dir)
+        .and_then(|()| {
+            setup_html_dir(
+                &args.dir,
+                &args.index_file,
+                !args.no_placeholder,
+                args.placeholder.as_deref(),
+                args.refresh,
+            )
+        })
+        .and_then(|()| std_fs::canonicalize(&args.dir));
+    match setup {
+        Ok(path) => info!("Serving reports from: {}", path.display()),
+        Err(e) => {
+            error!("Error setting up {}: {}", args.dir, e);
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+
+    // Shared by the server, the REPL, the watcher and the Ctrl+C handler
+    let shutdown = Arc::new(Shutdown::new());
+    let handler_shutdown = shutdown.clone();
+
+    // Channel used to tell open report pages to reload
+    let (events, _) = broadcast::channel(16);
+    let session = Arc::new(Session::new(args, runner, events));
+    let handler_session = session.clone();
+
+    // Shut down on Ctrl+C or SIGTERM
+    tokio::spawn(async move {
+        let signal = match shutdown_signal().await {
+            Ok(signal) => signal,
+            Err(e) => {
+                error!("Error listening for Ctrl+C: {}", e);
+                return;
+            }
+        };
+        info!("Received {}, shutting down...", signal);
+        shut_down(&handler_session, &handler_shutdown);
+    });
 
-        if let Err(e) = run_coverage(&python_cmd) {
-            eprintln!("Error running coverage: {}", e);
+    // Start HTTP server on the current runtime
+    let server_shutdown = shutdown.clone();
+    let server_session = session.clone();
+    let server_task = tokio::spawn(async move {
+        if let Err(e) = start_http_server(server_session, server_shutdown).await {
+            error!("HTTP server error: {}", e);
         }
+    });
+
+    info!("Coverage HTTP server started!");
 
-        println!("Current test path: {}", current_test_path);
+    // The prompt runs nothing by itself, so only the other modes wait
+    if !interactive && session.args.delay > 0 {
+        info!("Waiting {}s before the first run", session.args.delay);
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(session.args.delay)) => {}
+            _ = shutdown.requested() => {}
+        }
     }
 
-    // Cleanup and shutdown
-    running.store(false, Ordering::SeqCst);
+    // Re-run coverage whenever watched files change
+    let watch_task = session.args.watch.clone().map(|pattern| {
+        let session = session.clone();
+        let shutdown = shutdown.clone();
+        let debounce = Duration::from_millis(session.args.debounce);
+        let min_interval = Duration::from_secs(session.args.watch_min_interval);
+        tokio::task::spawn_blocking(move || {
+            let args = &session.args;
+            let watched =
+                watch::Filter::new(&args.watch_ext, &args.watch_ignore, !args.no_gitignore)
+                    .and_then(|filter| {
+                        watch::watch(&pattern, &filter, debounce, min_interval, &shutdown, || {
+                            session.run();
+                        })
+                    });
+            if let Err(e) = watched {
+                error!("Error watching files: {}", e);
+            }
+        })
+    });
+
+    // Set when shutting down leaves the prompt waiting for input
+    let mut prompt_abandoned = false;
 
-    if let Err(e) = thread::spawn(move || {
-        if let Err(e) = server_thread.join() {
-            eprintln!("Error joining server thread: {:?}", e);
+    // Skipped if Ctrl+C was pressed during --delay, leaving the branch below to return
+    if session.args.once && shutdown.is_running() {
+        // Single run without touching stdin, so this works when it isn't a terminal
+        let run_session = session.clone();
+        tokio::task::spawn_blocking(move || run_session.run())
+            .await
+            .map_err(io::Error::other)?;
+
+        if !session.args.exit && shutdown.is_running() {
+            info!("Serving the report until Ctrl+C is pressed");
+            shutdown.requested().await;
         }
-    })
-    .join()
+    } else if !interactive {
+        // Serving, and with `watch` re-running, until Ctrl+C
+        info!("Serving the report until Ctrl+C is pressed");
+        shutdown.requested().await;
+    } else {
+        // Reading stdin blocks, so the REPL lives on the blocking thread pool
+        let repl_session = session.clone();
+        let repl_shutdown = shutdown.clone();
+        let repl = tokio::task::spawn_blocking(move || repl::run(&repl_session, &repl_shutdown));
+        tokio::select! {
+            ended = repl => {
+                let ended = ended.map_err(io::Error::other)??;
+                if ended == repl::Ended::InputClosed && shutdown.is_running() {
+                    info!(
+                        "Input closed, shutting down (use --serve-after to run once and keep serving)"
+                    );
+                }
+            }
+            // A signal or POST /api/shutdown while the prompt waits for a line, which
+            // would otherwise hold up the graceful stop until something is typed
+            _ = shutdown.requested() => prompt_abandoned = true,
+        }
+    }
+
+    // Cleanup and shutdown
+    let interrupted = !shutdown.is_running();
+    shutdown.trigger();
+
+    if let Some(watch_task) = watch_task
+        && let Err(e) = watch_task.await
     {
-        eprintln!("Timed out waiting for server thread to join: {:?}", e);
+        error!("Error waiting for file watcher to stop: {}", e);
     }
 
-    println!("Goodbye!");
-    Ok(())
+    if let Err(e) = server_task.await {
+        error!("Error waiting for HTTP server to stop: {}", e);
+    }
+
+    if interrupted {
+        info!("Graceful shutdown complete");
+    }
+    info!("Goodbye!");
+    // The prompt's thread is still blocked reading stdin, and returning would wait for it
+    if prompt_abandoned {
+        process::exit(session.last_result().map_or(0, RunResult::code).into());
+    }
+    // Without any run, there is nothing to report
+    Ok(session
+        .last_result()
+        .map_or(ExitCode::SUCCESS, RunResult::exit_code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_path_with_spaces_is_a_single_argument() {
+        let dir = std::env::temp_dir().join(format!("coverage-http {}", process::id()));
+        let test_dir = dir.join("My Project");
+        std_fs::create_dir_all(&test_dir).unwrap();
+        let test_path = test_dir.to_str().unwrap();
+
+        let args = Args::parse_from(["coverage-http", "--no-persist"]);
+        let runner = runner::from_args(&CoverageCommand::program("true"), &args);
+        let session = Session::new(args, runner, broadcast::channel(1).0);
+        session.set_test_path(&shlex::try_quote(test_path).unwrap());
+
+        let commands = session.coverage_commands();
+        assert_eq!(commands[0].args.last().unwrap(), test_path);
+
+        // `test -d` only succeeds if the path reaches the subprocess intact
+        let check = CoverageCommand::new("test", ["-d", test_path]);
+        let outcome = run_coverage(
+            &[check],
+            &Mutex::new(None),
+            None,
+            false,
+            &broadcast::channel(1).0,
+        )
+        .unwrap();
+        std_fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(outcome.status, RunStatus::Succeeded);
+    }
+
+    #[actix_web::test]
+    async fn report_files_stay_within_the_directory() {
+        use actix_web::{http::StatusCode, test};
+
+        let dir = std::env::temp_dir().join(format!("coverage-http traversal {}", process::id()));
+        let report_dir = dir.join("htmlcov");
+        std_fs::create_dir_all(&report_dir).unwrap();
+        std_fs::write(report_dir.join("index.html"), "report").unwrap();
+        std_fs::write(dir.join("secret.txt"), "secret").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.join("secret.txt"), report_dir.join("link.txt")).unwrap();
+
+        let app = test::init_service(App::new().service(report_files(
+            report_dir.to_str().unwrap(),
+            "index.html",
+            false,
+            true,
+        )))
+        .await;
+        let status = async |uri| {
+            let request = test::TestRequest::get().uri(uri).to_request();
+            test::call_service(&app, request).await.status()
+        };
+        assert_eq!(status("/index.html").await, StatusCode::OK);
+        for uri in [
+            "/../secret.txt",
+            "/..%2fsecret.txt",
+            "/%2e%2e%2fsecret.txt",
+            "/..%5csecret.txt",
+            "/link.txt",
+        ] {
+            assert_ne!(status(uri).await, StatusCode::OK, "{}", uri);
+        }
+        std_fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn localhost_is_served_on_both_loopback_addresses() {
+        use tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::TcpStream,
+        };
+
+        let (listeners, port) = bind_listeners("localhost", 0, 0).await.unwrap();
+        let mut server =
+            HttpServer::new(|| App::new().route("/", web::get().to(|| async { "report" })));
+        for listener in listeners {
+            server = server.listen(listener).unwrap();
+        }
+        let server = server.disable_signals().run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let mut addresses = vec!["127.0.0.1".to_string()];
+        // Not every machine has IPv6, and then only IPv4 is served
+        if TcpListener::bind((Ipv6Addr::LOCALHOST, 0)).is_ok() {
+            addresses.push("[::1]".to_string());
+        }
+        for address in addresses {
+            let mut stream = TcpStream::connect(format!("{}:{}", address, port))
+                .await
+                .unwrap();
+            stream.write_all(b"GET / HTTP/1.0\r\n\r\n").await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            assert!(
+                response.starts_with("HTTP/1.0 200"),
+                "{}: {}",
+                address,
+                response
+            );
+            assert!(response.ends_with("report"), "{}: {}", address, response);
+        }
+        handle.stop(false).await;
+    }
 }
@@ -0,0 +1,52 @@
+use crate::{report::MIN_VISIBLE_DELTA, runner::TestCounts};
+use notify_rust::Notification;
+use std::thread;
+use tracing::warn;
+
+/// Describe the total and how it moved since the previous run
+fn describe_total(total: f64, previous_total: Option<f64>) -> String {
+    match previous_total.map(|previous| total - previous) {
+        Some(delta) if delta >= MIN_VISIBLE_DELTA => {
+            format!("Coverage {:.1}%, up {:.1} points", total, delta)
+        }
+        Some(delta) if delta <= -MIN_VISIBLE_DELTA => {
+            format!("Coverage {:.1}%, down {:.1} points", total, -delta)
+        }
+        Some(_) => format!("Coverage {:.1}%, unchanged", total),
+        None => format!("Coverage {:.1}%", total),
+    }
+}
+
+/// Show a desktop notification with the outcome of a finished run. It is sent from a
+/// separate thread so a slow notification service doesn't hold up the prompt, and
+/// where notifications aren't available only a warning is printed.
+pub fn run_finished(
+    failed: bool,
+    total: Option<f64>,
+    previous_total: Option<f64>,
+    tests: Option<TestCounts>,
+) {
+    let summary = if failed {
+        "Coverage run failed"
+    } else {
+        "Coverage run succeeded"
+    };
+    let mut body = Vec::new();
+    if let Some(total) = total {
+        body.push(describe_total(total, previous_total));
+    }
+    if let Some(tests) = tests {
+        body.push(format!("Tests: {}", tests));
+    }
+
+    let mut notification = Notification::new();
+    notification
+        .appname("coverage-http")
+        .summary(summary)
+        .body(&body.join("\n"));
+    thread::spawn(move || {
+        if let Err(e) = notification.show() {
+            warn!("Warning: could not show desktop notification: {}", e);
+        }
+    });
+}
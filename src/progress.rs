@@ -0,0 +1,108 @@
+use std::{
+    io::{self, Write},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+const FRAMES: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// How often the spinner advances
+const TICK: Duration = Duration::from_millis(100);
+
+/// Clears the current terminal line and moves the cursor to its start
+const CLEAR_LINE: &str = "\r\x1B[2K";
+
+struct State {
+    /// Whether the command's output ended with a newline, so the status line can be
+    /// drawn without breaking up a partial line such as pytest's progress dots
+    at_line_start: bool,
+    /// Whether the status line is currently on screen
+    shown: bool,
+    frame: usize,
+}
+
+/// Spinner and elapsed time shown on the last terminal line while a command runs. The
+/// command's output goes through `write`, which keeps the status line below it.
+pub struct Progress {
+    label: String,
+    start: Instant,
+    state: Mutex<State>,
+    done: AtomicBool,
+}
+
+impl Progress {
+    /// Show the status line until `finish` is called
+    pub fn start(label: String) -> Arc<Self> {
+        let progress = Arc::new(Self {
+            label,
+            start: Instant::now(),
+            state: Mutex::new(State {
+                at_line_start: true,
+                shown: false,
+                frame: 0,
+            }),
+            done: AtomicBool::new(false),
+        });
+
+        let ticker = progress.clone();
+        thread::spawn(move || {
+            while !ticker.done.load(Ordering::SeqCst) {
+                ticker.draw(&mut ticker.state.lock().unwrap());
+                thread::sleep(TICK);
+            }
+        });
+        progress
+    }
+
+    fn draw(&self, state: &mut State) {
+        if !state.at_line_start || self.done.load(Ordering::SeqCst) {
+            return;
+        }
+        let frame = FRAMES[state.frame % FRAMES.len()];
+        state.frame += 1;
+        let mut stdout = io::stdout().lock();
+        let _ = write!(
+            stdout,
+            "{}{} {}, {}s",
+            CLEAR_LINE,
+            frame,
+            self.label,
+            self.start.elapsed().as_secs()
+        );
+        let _ = stdout.flush();
+        state.shown = true;
+    }
+
+    fn clear(state: &mut State) {
+        if state.shown {
+            let mut stdout = io::stdout().lock();
+            let _ = write!(stdout, "{}", CLEAR_LINE);
+            let _ = stdout.flush();
+            state.shown = false;
+        }
+    }
+
+    /// Write output of the command to `out`, moving the status line below it
+    pub fn write(&self, out: &mut impl Write, output: &[u8]) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        Self::clear(&mut state);
+        out.write_all(output)?;
+        out.flush()?;
+        if let Some(last) = output.last() {
+            state.at_line_start = *last == b'\n';
+        }
+        self.draw(&mut state);
+        Ok(())
+    }
+
+    /// Remove the status line for good
+    pub fn finish(&self) {
+        let mut state = self.state.lock().unwrap();
+        self.done.store(true, Ordering::SeqCst);
+        Self::clear(&mut state);
+    }
+}
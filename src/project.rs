@@ -0,0 +1,81 @@
+use std::fs;
+
+/// `testpaths` from `[tool.pytest.ini_options]` in a pyproject.toml
+fn pyproject_testpaths(contents: &str) -> Option<Vec<String>> {
+    let table: toml::Table = contents.parse().ok()?;
+    let testpaths = table
+        .get("tool")?
+        .get("pytest")?
+        .get("ini_options")?
+        .get("testpaths")?;
+
+    match testpaths {
+        toml::Value::String(path) => Some(vec![path.clone()]),
+        toml::Value::Array(paths) => Some(
+            paths
+                .iter()
+                .filter_map(|path| path.as_str().map(String::from))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// `testpaths` from the given section of an INI file, which may continue on indented lines
+fn ini_testpaths(contents: &str, section: &str) -> Option<Vec<String>> {
+    let mut in_section = false;
+    let mut value: Option<String> = None;
+
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if let Some(header) = trimmed.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+            if value.is_some() {
+                break;
+            }
+            in_section = header.trim() == section;
+            continue;
+        }
+        if !in_section || trimmed.is_empty() || trimmed.starts_with(['#', ';']) {
+            continue;
+        }
+
+        let is_continuation = line.starts_with([' ', '\t']);
+        match &mut value {
+            Some(value) if is_continuation => {
+                value.push(' ');
+                value.push_str(trimmed);
+            }
+            Some(_) => break,
+            None => {
+                if let Some((key, rest)) = trimmed.split_once(['=', ':'])
+                    && key.trim() == "testpaths"
+                {
+                    value = Some(rest.trim().to_string());
+                }
+            }
+        }
+    }
+
+    Some(value?.split_whitespace().map(String::from).collect())
+}
+
+/// Test paths declared in the project's pytest configuration and the file declaring them,
+/// looked up the way pytest does: pytest.ini, then pyproject.toml, then setup.cfg
+pub fn pytest_testpaths() -> Option<(&'static str, Vec<String>)> {
+    let read = |file| fs::read_to_string(file).ok();
+
+    read("pytest.ini")
+        .and_then(|contents| ini_testpaths(&contents, "pytest"))
+        .map(|paths| ("pytest.ini", paths))
+        .or_else(|| {
+            read("pyproject.toml")
+                .and_then(|contents| pyproject_testpaths(&contents))
+                .map(|paths| ("pyproject.toml", paths))
+        })
+        .or_else(|| {
+            read("setup.cfg")
+                .and_then(|contents| ini_testpaths(&contents, "tool:pytest"))
+                .map(|paths| ("setup.cfg", paths))
+        })
+        .filter(|(_, paths)| !paths.is_empty())
+}
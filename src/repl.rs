@@ -0,0 +1,228 @@
+use crate::{Session, history, open_browser, report, shutdown::Shutdown};
+use rustyline::{
+    Completer, CompletionType, Config, Editor, Helper, Highlighter, Hinter, Validator,
+    completion::FilenameCompleter, error::ReadlineError, history::DefaultHistory,
+};
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+use tracing::{error, info};
+
+/// Lines entered at the prompt, kept in the current directory for up-arrow recall and
+/// Ctrl+R search in later sessions
+const PROMPT_HISTORY_FILE: &str = ".coverage-http-prompt-history";
+
+/// Number of runs listed by `history` without an explicit count
+const DEFAULT_HISTORY_COUNT: usize = 10;
+
+/// A line entered at the prompt. Command names are case-insensitive; anything else is
+/// taken as a new test path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Input {
+    /// Run coverage, after switching to the given test path if there is one
+    Run(Option<String>),
+    /// Run only the tests that failed last time
+    Retry,
+    History(usize),
+    Status,
+    /// Open the report in the browser, at the page of the given source file if there is one
+    Open(Option<String>),
+    Clear,
+    Help,
+    Exit,
+}
+
+impl Input {
+    /// Parse a line, returning the usage of the command on bad arguments
+    fn parse(line: &str) -> Result<Self, &'static str> {
+        let line = line.trim();
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default().to_lowercase();
+        let argument = words.next();
+        // Everything after the command, for arguments that may contain spaces
+        let rest = line
+            .split_once(char::is_whitespace)
+            .map(|(_, rest)| rest.trim().to_string());
+
+        let input = match command.as_str() {
+            "" => Input::Run(None),
+            "history" => match argument.map_or(Ok(DEFAULT_HISTORY_COUNT), str::parse) {
+                Ok(count) => Input::History(count),
+                Err(_) => return Err("history [N]"),
+            },
+            "retry" => Input::Retry,
+            "status" => Input::Status,
+            "open" => Input::Open(rest),
+            "clear" => Input::Clear,
+            "help" => Input::Help,
+            "exit" => Input::Exit,
+            _ => Input::Run(Some(line.to_string())),
+        };
+        Ok(input)
+    }
+}
+
+const HELP: &str = "\
+Commands:
+  <Enter>       run coverage with the current test path
+  <path>        switch to a new test path and run coverage
+  retry         re-run only the tests that failed in the last run
+  history [N]   list the totals of the last N runs (default: 10)
+  status        show the test path, report URL and last run
+  open [FILE]   open the report in the browser, at FILE's page if given
+  clear         clear the screen
+  help          show this help
+  exit          quit";
+
+/// Completes file and directory names relative to the working directory, like a shell
+#[derive(Helper, Completer, Hinter, Highlighter, Validator)]
+struct PromptHelper {
+    #[rustyline(Completer)]
+    filenames: FilenameCompleter,
+}
+
+/// Print the current test path, where the report is served and how the last run went
+fn print_status(session: &Session) {
+    println!("Test path: {}", session.test_path());
+    match (session.url(), &session.args.unix_socket) {
+        (Some(url), _) => println!("Report: {}", url),
+        (None, Some(path)) => println!("Report: on Unix socket {}", path.display()),
+        (None, None) => println!("Report: server not started yet"),
+    }
+    match session.last_run_at() {
+        Some(time) => {
+            let outcome = if session.last_run_failed() {
+                "failed"
+            } else {
+                "succeeded"
+            };
+            println!(
+                "Last run: {} at {}",
+                outcome,
+                time.format("%Y-%m-%d %H:%M:%S")
+            );
+        }
+        None => println!("Last run: none yet"),
+    }
+    match session.last_total() {
+        Some(total) => match session.last_commit() {
+            Some(commit) => println!("Total coverage: {:.1}% at commit {}", total, commit),
+            None => println!("Total coverage: {:.1}%", total),
+        },
+        None => println!("Total coverage: unknown"),
+    }
+}
+
+/// Page of the report for the source file at `path`, or `None` after saying why there is
+/// none, in which case the index is opened instead
+fn report_page(session: &Session, path: &str) -> Option<String> {
+    match report::html_page(Path::new(&session.args.dir), path) {
+        Ok(Some(page)) => Some(page),
+        Ok(None) => {
+            println!("The report has no page for {}, opening the index", path);
+            None
+        }
+        Err(e) => {
+            println!(
+                "Could not look up the page for {} ({}), opening the index",
+                path, e
+            );
+            None
+        }
+    }
+}
+
+/// Why the prompt stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ended {
+    /// The user typed `exit` or pressed Ctrl+C, or the tool is shutting down
+    Exit,
+    /// Stdin was closed, such as a pipe running out or the process being detached
+    InputClosed,
+}
+
+/// Interactive prompt that runs coverage on Enter until the user exits or stdin closes
+pub fn run(session: &Session, shutdown: &Shutdown) -> io::Result<Ended> {
+    info!("Press Enter to run coverage tests with the current test path, or enter a new path");
+    info!("Type `help` to list the other commands");
+    info!("Current test path: {}", session.test_path());
+
+    let config = Config::builder()
+        .completion_type(CompletionType::List)
+        .build();
+    let mut editor: Editor<PromptHelper, DefaultHistory> =
+        Editor::with_config(config).map_err(io::Error::other)?;
+    editor.set_helper(Some(PromptHelper {
+        filenames: FilenameCompleter::new(),
+    }));
+    let persist = !session.args.no_persist;
+    if persist {
+        // Missing on first use
+        let _ = editor.load_history(PROMPT_HISTORY_FILE);
+    }
+
+    while shutdown.is_running() {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            // Rather than read as an empty line, which would run coverage over and over
+            Err(ReadlineError::Eof) => return Ok(Ended::InputClosed),
+            // Ctrl+C at the prompt doesn't raise SIGINT while the editor owns the terminal
+            Err(ReadlineError::Interrupted) => break,
+            Err(e) => return Err(io::Error::other(e)),
+        };
+        if !line.trim().is_empty() {
+            let _ = editor.add_history_entry(line.trim());
+            if persist && let Err(e) = editor.save_history(PROMPT_HISTORY_FILE) {
+                error!(
+                    "Error saving prompt history to {}: {}",
+                    PROMPT_HISTORY_FILE, e
+                );
+            }
+        }
+
+        let input = match Input::parse(&line) {
+            Ok(input) => input,
+            Err(usage) => {
+                eprintln!("Usage: {}", usage);
+                continue;
+            }
+        };
+        match input {
+            Input::Run(path) => {
+                if let Some(path) = path {
+                    session.set_test_path(&path);
+                    println!("Test path updated to: {}", path);
+                }
+                session.run();
+                println!("Current test path: {}", session.test_path());
+            }
+            Input::Retry => session.retry(),
+            Input::History(count) => match history::load(Path::new(&session.args.dir)) {
+                Ok(entries) => history::print_recent(&entries, count),
+                Err(e) => error!("Error reading coverage history: {}", e),
+            },
+            Input::Status => print_status(session),
+            Input::Open(file) => match session.url() {
+                Some(url) => {
+                    let page = file.and_then(|file| report_page(session, &file));
+                    if let Err(e) = open_browser(&format!("{}{}", url, page.unwrap_or_default())) {
+                        error!("Could not open browser: {}", e);
+                    }
+                }
+                None if session.args.unix_socket.is_some() => {
+                    eprintln!("The report is served on a Unix socket, open it through its proxy")
+                }
+                None => eprintln!("The server hasn't started yet"),
+            },
+            Input::Clear => {
+                print!("\x1B[2J\x1B[H");
+                io::stdout().flush()?;
+            }
+            Input::Help => println!("{}", HELP),
+            Input::Exit => break,
+        }
+    }
+
+    Ok(Ended::Exit)
+}
@@ -0,0 +1,326 @@
+use crate::CoverageCommand;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, IsTerminal},
+    path::Path,
+    process::Command,
+};
+
+/// Extract the total percentage from the `TOTAL` line of `coverage report` output
+pub fn parse_total(report: &str) -> Option<f64> {
+    report
+        .lines()
+        .find(|line| line.trim_start().starts_with("TOTAL"))?
+        .split_whitespace()
+        .last()?
+        .trim_end_matches('%')
+        .parse()
+        .ok()
+}
+
+/// Coverage percentage of each file in `coverage report` output, in the order listed
+pub fn parse_files(report: &str) -> Vec<(String, f64)> {
+    report
+        .lines()
+        .filter(|line| !line.starts_with('-') && !line.trim_start().starts_with("TOTAL"))
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let percent = columns.last()?.strip_suffix('%')?.parse().ok()?;
+            // The path is everything before the counts, as it may contain spaces
+            let counts = columns
+                .iter()
+                .rev()
+                .take_while(|column| column.trim_end_matches('%').parse::<f64>().is_ok())
+                .count();
+            let path = columns[..columns.len() - counts].join(" ");
+            (!path.is_empty()).then_some((path, percent))
+        })
+        .collect()
+}
+
+/// Run `coverage report` with the given interpreter and return the total coverage
+/// percentage
+pub fn total_coverage(python: &CoverageCommand) -> io::Result<Option<f64>> {
+    Ok(parse_total(&text_report(python)?))
+}
+
+/// Run `coverage report` with the given interpreter and return the coverage percentage
+/// of each file
+pub fn file_coverage(python: &CoverageCommand) -> io::Result<Vec<(String, f64)>> {
+    Ok(parse_files(&text_report(python)?))
+}
+
+/// Output of `coverage report` run with the given interpreter
+fn text_report(python: &CoverageCommand) -> io::Result<String> {
+    let command = python.with_args(["-m", "coverage", "report"]);
+    let output = Command::new(&command.program)
+        .args(&command.args)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "coverage report exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// File name of the `coverage json` output written into the report directory
+pub const JSON_REPORT: &str = "coverage.json";
+
+/// Coverage counts from `coverage json`, for the whole project or a single file.
+/// Branch counts are only present when branch coverage was measured.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Summary {
+    pub num_statements: u64,
+    pub covered_lines: u64,
+    pub missing_lines: u64,
+    #[serde(default)]
+    pub excluded_lines: u64,
+    pub percent_covered: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_branches: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub covered_branches: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing_branches: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_partial_branches: Option<u64>,
+}
+
+impl Summary {
+    /// Percentage of branches taken both ways, if branches were measured
+    pub fn branch_percent(&self) -> Option<f64> {
+        let branches = self.num_branches.filter(|&branches| branches > 0)?;
+        Some(100.0 * self.covered_branches? as f64 / branches as f64)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileReport {
+    pub summary: Summary,
+    /// Statements that never ran
+    #[serde(default)]
+    pub missing_lines: Vec<u64>,
+}
+
+/// The parts of a `coverage json` report this tool uses
+#[derive(Debug, Clone, Deserialize)]
+pub struct JsonReport {
+    pub totals: Summary,
+    pub files: BTreeMap<String, FileReport>,
+}
+
+impl JsonReport {
+    pub fn parse(json: &[u8]) -> serde_json::Result<Self> {
+        serde_json::from_slice(json)
+    }
+}
+
+/// Load the `coverage json` report written into `dir`
+pub fn load_json(dir: &Path) -> io::Result<JsonReport> {
+    let bytes = fs::read(dir.join(JSON_REPORT))?;
+    Ok(JsonReport::parse(&bytes)?)
+}
+
+/// Index of the HTML report coverage.py keeps next to it, mapping source files to their
+/// pages. Older versions name the fields differently.
+const HTML_STATUS: &str = "status.json";
+
+#[derive(Debug, Deserialize)]
+struct HtmlStatus {
+    files: BTreeMap<String, HtmlStatusFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HtmlStatusFile {
+    index: HtmlStatusIndex,
+}
+
+#[derive(Debug, Deserialize)]
+struct HtmlStatusIndex {
+    #[serde(alias = "html_filename")]
+    url: String,
+    #[serde(alias = "relative_filename")]
+    file: String,
+}
+
+/// Page of the HTML report in `dir` for the source file at `path`, relative to the report
+/// directory, or `None` if the report has no page for it. A path that isn't an exact
+/// match is looked up as the end of a single measured path, so `foo.py` finds
+/// `src/pkg/foo.py` unless there are several.
+pub fn html_page(dir: &Path, path: &str) -> io::Result<Option<String>> {
+    let status: HtmlStatus = serde_json::from_slice(&fs::read(dir.join(HTML_STATUS))?)?;
+    let path = path.trim_start_matches("./").replace('\\', "/");
+    let pages: Vec<_> = status
+        .files
+        .into_values()
+        .map(|file| (file.index.file.replace('\\', "/"), file.index.url))
+        .collect();
+
+    if let Some((_, url)) = pages.iter().find(|(file, _)| *file == path) {
+        return Ok(Some(url.clone()));
+    }
+    let suffix = format!("/{}", path);
+    let mut matches = pages
+        .into_iter()
+        .filter(|(file, _)| file.ends_with(&suffix));
+    Ok(match (matches.next(), matches.next()) {
+        (Some((_, url)), None) => Some(url),
+        _ => None,
+    })
+}
+
+/// Wrap text in an ANSI color when stdout is a terminal
+fn paint(text: &str, color: u8) -> String {
+    if io::stdout().is_terminal() {
+        format!("\x1b[{}m{}\x1b[0m", color, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Smallest change that shows up when rounding to one decimal
+pub const MIN_VISIBLE_DELTA: f64 = 0.05;
+
+/// Signed change in percentage points, green for gains and red for losses
+pub fn format_delta(delta: f64) -> String {
+    if delta >= MIN_VISIBLE_DELTA {
+        paint(&format!("{:+.1}%", delta), 32)
+    } else if delta <= -MIN_VISIBLE_DELTA {
+        paint(&format!("{:+.1}%", delta), 31)
+    } else {
+        "+0.0%".to_string()
+    }
+}
+
+/// Print the `count` files with the lowest coverage, leaving out fully covered ones
+pub fn print_worst(files: &[(String, f64)], count: usize) {
+    let mut worst: Vec<_> = files
+        .iter()
+        .filter(|(_, percent)| *percent < 100.0)
+        .collect();
+    if worst.is_empty() {
+        return;
+    }
+    worst.sort_by(|a, b| a.1.total_cmp(&b.1));
+    println!("Lowest coverage:");
+    for (path, percent) in worst.into_iter().take(count) {
+        println!("  {}: {:.1}%", path, percent);
+    }
+}
+
+/// Print the files whose coverage dropped from one report to the next
+pub fn print_regressions(previous: &JsonReport, current: &JsonReport) {
+    let regressions: Vec<_> = current
+        .files
+        .iter()
+        .filter_map(|(path, file)| {
+            let before = previous.files.get(path)?.summary.percent_covered;
+            let after = file.summary.percent_covered;
+            ((after - before) * 10.0)
+                .round()
+                .lt(&0.0)
+                .then_some((path, before, after))
+        })
+        .collect();
+
+    if regressions.is_empty() {
+        return;
+    }
+    println!("Coverage dropped in:");
+    for (path, before, after) in regressions {
+        println!(
+            "  {}: {:.1}% -> {:.1}% ({})",
+            path,
+            before,
+            after,
+            format_delta(after - before)
+        );
+    }
+}
+
+/// Escape a property of a GitHub Actions workflow command, such as a file name
+fn escape_github_property(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// Print a GitHub Actions warning for each range of consecutive uncovered statements in
+/// `current` that were covered in `previous`, which shows up as an annotation on the
+/// lines. Without a previous report, or for files new to it, every uncovered statement
+/// is reported.
+pub fn print_github_annotations(previous: Option<&JsonReport>, current: &JsonReport) {
+    for (path, file) in &current.files {
+        let previously_missing = previous
+            .and_then(|previous| previous.files.get(path))
+            .map(|file| &file.missing_lines);
+        let mut lines = file
+            .missing_lines
+            .iter()
+            .copied()
+            .filter(|line| previously_missing.is_none_or(|missing| !missing.contains(line)))
+            .peekable();
+
+        let file = escape_github_property(path);
+        while let Some(start) = lines.next() {
+            let mut end = start;
+            while lines.next_if_eq(&(end + 1)).is_some() {
+                end += 1;
+            }
+            if start == end {
+                println!("::warning file={},line={}::Not covered", file, start);
+            } else {
+                println!(
+                    "::warning file={},line={},endLine={}::Not covered",
+                    file, start, end
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = "\
+Name                Stmts   Miss  Cover
+---------------------------------------
+src/a.py               10      5    50%
+src/c b.py             10      9    10%
+---------------------------------------
+TOTAL                  20     14    30%
+";
+
+    #[test]
+    fn total_is_read_from_the_total_line() {
+        assert_eq!(parse_total(REPORT), Some(30.0));
+        // With --precision and branch columns
+        let report = "TOTAL    120     10     40      5    88.46%\n";
+        assert_eq!(parse_total(report), Some(88.46));
+        assert_eq!(parse_total("No data to report.\n"), None);
+    }
+
+    #[test]
+    fn files_keep_the_spaces_in_their_paths() {
+        assert_eq!(
+            parse_files(REPORT),
+            vec![
+                ("src/a.py".to_string(), 50.0),
+                ("src/c b.py".to_string(), 10.0)
+            ]
+        );
+        let branches = "src/d.py    10   2   4   1   75%\n";
+        assert_eq!(parse_files(branches), vec![("src/d.py".to_string(), 75.0)]);
+    }
+}
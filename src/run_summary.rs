@@ -0,0 +1,52 @@
+use crate::{RunResult, report::Summary, runner::TestCounts};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// Coverage of one file, with the full counts when the `coverage json` report has them
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum FileStats {
+    Counts(Summary),
+    Percent { percent_covered: f64 },
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileSummary {
+    pub path: String,
+    #[serde(flatten)]
+    pub stats: FileStats,
+}
+
+/// Everything known about a finished run, for tools that read it from disk
+#[derive(Debug, Serialize)]
+pub struct RunSummary {
+    pub result: RunResult,
+    pub timestamp: DateTime<Utc>,
+    /// Seconds the run took, unless its commands couldn't be started
+    pub duration: Option<f64>,
+    /// Abbreviated hash of the commit the run tested, if in a git repository
+    pub commit: Option<String>,
+    /// Total coverage, unless the run failed or it couldn't be read
+    pub total: Option<f64>,
+    /// Number of tests per outcome, if the test runner's output had them
+    pub tests: Option<TestCounts>,
+    /// Empty when the run failed, as the report may be stale
+    pub files: Vec<FileSummary>,
+}
+
+/// Write `summary` as JSON to `path`. It goes to a temporary file renamed over the old
+/// one, so a dashboard polling the file never reads it half written.
+pub fn write(path: &Path, summary: &RunSummary) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let mut file = fs::File::create(&temporary)?;
+    serde_json::to_writer_pretty(&mut file, summary)?;
+    writeln!(file)?;
+    file.sync_all()?;
+    fs::rename(&temporary, path)
+}
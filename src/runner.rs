@@ -0,0 +1,664 @@
+use crate::{Args, CoverageCommand, TestRunner, report};
+use serde::{Serialize, Serializer};
+use std::{
+    fmt, fs, io, iter,
+    path::Path,
+    process::{Command, Stdio},
+    str::FromStr,
+};
+use tracing::warn;
+
+/// A coverage tool: the commands producing its report and how to read the total back
+pub trait CoverageRunner: Send + Sync {
+    /// Check that the tools needed for a run are installed, printing how to install any
+    /// that are missing. Returns whether all of them were found.
+    fn verify_tools(&self) -> bool;
+
+    /// Commands making up a coverage run of the given test paths, run in order
+    fn commands(&self, test_paths: &[String]) -> Vec<CoverageCommand>;
+
+    /// Commands re-running only the tests of the given paths that failed last time,
+    /// adding their coverage to the data of the previous run, or `None` if the runner
+    /// can't tell which tests failed
+    fn rerun_failed_commands(&self, _test_paths: &[String]) -> Option<Vec<CoverageCommand>> {
+        None
+    }
+
+    /// Total coverage percentage of the last run, or `None` if it can't be found in the
+    /// tool's output
+    fn parse_total(&self) -> io::Result<Option<f64>>;
+
+    /// Coverage percentage of each file in the last run, from the tool's text report, for
+    /// when there is no `coverage json` report to read it from. Empty if the runner has
+    /// no such report.
+    fn file_coverage(&self) -> io::Result<Vec<(String, f64)>> {
+        Ok(Vec::new())
+    }
+
+    /// How many tests passed, failed and so on, read from what the run's commands wrote
+    /// to stdout, or `None` if the runner's summary isn't in there
+    fn parse_test_counts(&self, _stdout: &str) -> Option<TestCounts> {
+        None
+    }
+}
+
+/// Number of tests per outcome, such as `120 passed` or `2 errors`, in the order the
+/// test runner printed them. Empty if no tests ran.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCounts(Vec<(u64, String)>);
+
+impl fmt::Display for TestCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("no tests ran");
+        }
+        let counts: Vec<String> = self
+            .0
+            .iter()
+            .map(|(count, outcome)| format!("{} {}", count, outcome))
+            .collect();
+        f.write_str(&counts.join(", "))
+    }
+}
+
+/// Serialized as an object from each outcome to its count, such as `{"passed": 120}`
+impl Serialize for TestCounts {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(count, outcome)| (outcome, count)))
+    }
+}
+
+/// Remove the ANSI escape sequences tools add to colored output
+pub fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1B' {
+            // Skip `[`, the parameters and the final letter
+            chars.find(char::is_ascii_alphabetic);
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// Extract the outcome counts from the last pytest summary line, such as
+/// `===== 5 failed, 120 passed, 2 warnings in 3.21s =====`. Lenient about what pytest
+/// versions differ in: the `=` padding, colors, which outcomes are listed and how the
+/// duration is written.
+fn parse_pytest_summary(stdout: &str) -> Option<TestCounts> {
+    stdout.lines().rev().find_map(|line| {
+        let line = strip_ansi(line);
+        let (counts, duration) = line.trim().trim_matches('=').trim().rsplit_once(" in ")?;
+        if !duration.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        if counts == "no tests ran" {
+            return Some(TestCounts(Vec::new()));
+        }
+        counts
+            .split(", ")
+            .map(|count| {
+                let (number, outcome) = count.split_once(' ')?;
+                if !outcome.chars().all(|c| c.is_ascii_alphabetic()) {
+                    return None;
+                }
+                Some((number.parse().ok()?, outcome.to_string()))
+            })
+            .collect::<Option<_>>()
+            .map(TestCounts)
+    })
+}
+
+/// Whether the command runs and exits successfully, with its output discarded
+fn succeeds(command: &CoverageCommand) -> bool {
+    Command::new(&command.program)
+        .args(&command.args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Python test runners that coverage.py can run as a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PythonTestRunner {
+    Pytest,
+    Unittest,
+}
+
+impl PythonTestRunner {
+    /// Module and arguments passed to `coverage run -m` to test the given paths
+    fn module_args(self, test_paths: &[String]) -> Vec<String> {
+        let module: &[&str] = match self {
+            PythonTestRunner::Pytest => &["pytest"],
+            PythonTestRunner::Unittest => &["unittest", "discover", "-s"],
+        };
+        module
+            .iter()
+            .map(|arg| arg.to_string())
+            .chain(test_paths.iter().cloned())
+            .collect()
+    }
+}
+
+/// pytest arguments running only the tests that failed last time. Without any recorded
+/// failures, such as with a missing `.pytest_cache`, pytest would otherwise run them all.
+const LAST_FAILED_ARGS: &[&str] = &["--last-failed", "--last-failed-no-failures=none"];
+
+/// coverage.py running pytest or unittest, writing both the HTML and the JSON report
+pub struct PythonCoverageRunner {
+    /// Invocation of the interpreter, such as `python` or `poetry run python`
+    python: CoverageCommand,
+    test_runner: PythonTestRunner,
+    runner_args: Vec<String>,
+    report_dir: String,
+    /// Whether to erase the data of previous runs first
+    erase: bool,
+    /// Number of pytest-xdist workers, if the tests run in parallel. Their coverage is
+    /// measured by pytest-cov, as `coverage run` doesn't follow the worker processes.
+    jobs: Option<u32>,
+    /// Where to also write a Cobertura XML report, if anywhere
+    xml_report: Option<String>,
+    /// Where to also write an LCOV report, if anywhere
+    lcov_report: Option<String>,
+    /// Whether to measure branch coverage too
+    branch: bool,
+    /// Label the coverage data is attributed to, if any
+    context: Option<String>,
+    /// Comma-separated globs of files to leave out of the measurement, if any
+    omit: Option<String>,
+    /// Arguments of separate `coverage run` invocations replacing the test runner, whose
+    /// data is combined into one report. Empty to run the test runner.
+    runs: Vec<Vec<String>>,
+}
+
+impl PythonCoverageRunner {
+    /// Commands of a run of the given test paths. With `rerun_failed`, pytest only runs
+    /// the tests that failed last time, and their coverage is added to the existing data
+    /// instead of replacing it, so the report still covers the whole suite. With `runs`
+    /// configured, those are run instead and the test paths aren't used.
+    fn build_commands(&self, test_paths: &[String], rerun_failed: bool) -> Vec<CoverageCommand> {
+        let python = &self.python;
+        let mut commands = match self.jobs {
+            Some(jobs) if self.runs.is_empty() => {
+                vec![self.xdist_command(test_paths, jobs, rerun_failed)]
+            }
+            _ => self.coverage_run_commands(test_paths, rerun_failed),
+        };
+
+        let json_report = Path::new(&self.report_dir).join(report::JSON_REPORT);
+        let json_report = json_report.to_string_lossy();
+        let mut html_args = vec!["-m", "coverage", "html"];
+        if self.context.is_some() {
+            // The report only shows which context covered a line when asked to
+            html_args.push("--show-contexts");
+        }
+        commands.push(python.with_args(html_args));
+        if let Some(xml_report) = &self.xml_report {
+            commands.push(python.with_args(["-m", "coverage", "xml", "-o", xml_report]));
+        }
+        if let Some(lcov_report) = &self.lcov_report {
+            commands.push(python.with_args(["-m", "coverage", "lcov", "-o", lcov_report]));
+        }
+        commands.push(python.with_args(["-m", "coverage", "json", "-o", &json_report]));
+        commands
+    }
+
+    /// pytest on `jobs` pytest-xdist workers, measured by pytest-cov, which starts
+    /// coverage in each worker and combines their data when the tests are done
+    fn xdist_command(
+        &self,
+        test_paths: &[String],
+        jobs: u32,
+        rerun_failed: bool,
+    ) -> CoverageCommand {
+        let mut args = vec!["-m".to_string()];
+        args.extend(self.test_runner.module_args(test_paths));
+        args.extend(["-n".to_string(), jobs.to_string()]);
+        // Measure without printing a report, as the reports are written below
+        args.extend(["--cov".to_string(), "--cov-report=".to_string()]);
+        if self.branch {
+            args.push("--cov-branch".to_string());
+        }
+        // pytest-cov erases the previous data unless told to add to it
+        if rerun_failed {
+            args.extend(LAST_FAILED_ARGS.iter().map(|arg| arg.to_string()));
+            args.push("--cov-append".to_string());
+        }
+        args.extend(self.runner_args.iter().cloned());
+        self.python.with_args(args)
+    }
+
+    /// `coverage run` of the test runner, or of each configured run
+    fn coverage_run_commands(
+        &self,
+        test_paths: &[String],
+        rerun_failed: bool,
+    ) -> Vec<CoverageCommand> {
+        let python = &self.python;
+        // What follows `coverage run` and its options in each invocation
+        let runs: Vec<Vec<String>> = if self.runs.is_empty() {
+            // unittest discovers tests below a single directory, so it gets one run per
+            // path, each appending to the data of the ones before
+            let path_groups: Vec<&[String]> = match self.test_runner {
+                PythonTestRunner::Pytest => vec![test_paths],
+                PythonTestRunner::Unittest => test_paths.chunks(1).collect(),
+            };
+            path_groups
+                .into_iter()
+                .map(|paths| {
+                    iter::once("-m".to_string())
+                        .chain(self.test_runner.module_args(paths))
+                        .chain(
+                            LAST_FAILED_ARGS
+                                .iter()
+                                .filter(|_| rerun_failed)
+                                .map(|arg| arg.to_string()),
+                        )
+                        .chain(self.runner_args.iter().cloned())
+                        .collect()
+                })
+                .collect()
+        } else {
+            self.runs.clone()
+        };
+        // Each configured run writes its own data file, combined below
+        let parallel = !self.runs.is_empty();
+
+        // Files left out of the data never reach the reports either
+        let omit = self.omit.as_ref().map(|omit| format!("--omit={}", omit));
+        let context = self
+            .context
+            .as_ref()
+            .map(|context| format!("--context={}", context));
+        let mut commands = Vec::new();
+        // Data files left by configured runs that were cut short would be combined too
+        if (self.erase || !self.runs.is_empty()) && !rerun_failed {
+            commands.push(python.with_args(["-m", "coverage", "erase"]));
+        }
+        commands.extend(runs.into_iter().enumerate().map(|(i, run)| {
+            let mut run_args = vec!["-m", "coverage", "run"];
+            if parallel {
+                run_args.push("--parallel-mode");
+            } else if i > 0 || rerun_failed {
+                run_args.push("--append");
+            }
+            if self.branch {
+                run_args.push("--branch");
+            }
+            if let Some(omit) = &omit {
+                run_args.push(omit);
+            }
+            if let Some(context) = &context {
+                run_args.push(context);
+            }
+            python.with_args(run_args.into_iter().map(String::from).chain(run))
+        }));
+
+        if parallel {
+            let mut combine_args = vec!["-m", "coverage", "combine"];
+            if rerun_failed {
+                combine_args.push("--append");
+            }
+            commands.push(python.with_args(combine_args));
+        }
+        commands
+    }
+}
+
+impl CoverageRunner for PythonCoverageRunner {
+    fn verify_tools(&self) -> bool {
+        // Package to install, and the arguments that only succeed once it is
+        let mut packages: Vec<(&str, &[&str])> =
+            vec![("coverage", &["-m", "coverage", "--version"])];
+        if self.test_runner == PythonTestRunner::Pytest {
+            packages.push(("pytest", &["-m", "pytest", "--version"]));
+        }
+        if self.jobs.is_some() && self.runs.is_empty() {
+            packages.push(("pytest-xdist", &["-c", "import xdist"]));
+            packages.push(("pytest-cov", &["-c", "import pytest_cov"]));
+        }
+
+        let python = &self.python;
+        let mut all_found = true;
+        for (package, check) in packages {
+            if !succeeds(&python.with_args(check.iter().copied())) {
+                warn!(
+                    "{} not installed for {}; run `{} -m pip install {}`",
+                    package, python, python, package
+                );
+                all_found = false;
+            }
+        }
+        all_found
+    }
+
+    fn commands(&self, test_paths: &[String]) -> Vec<CoverageCommand> {
+        self.build_commands(test_paths, false)
+    }
+
+    fn rerun_failed_commands(&self, test_paths: &[String]) -> Option<Vec<CoverageCommand>> {
+        // unittest has no record of which tests failed, and configured runs may not be
+        // pytest at all
+        (self.test_runner == PythonTestRunner::Pytest && self.runs.is_empty())
+            .then(|| self.build_commands(test_paths, true))
+    }
+
+    fn parse_total(&self) -> io::Result<Option<f64>> {
+        report::total_coverage(&self.python)
+    }
+
+    fn file_coverage(&self) -> io::Result<Vec<(String, f64)>> {
+        report::file_coverage(&self.python)
+    }
+
+    fn parse_test_counts(&self, stdout: &str) -> Option<TestCounts> {
+        match self.test_runner {
+            PythonTestRunner::Pytest => parse_pytest_summary(stdout),
+            PythonTestRunner::Unittest => None,
+        }
+    }
+}
+
+/// `cargo llvm-cov` for Rust projects. The test path isn't used; the whole workspace
+/// is tested unless narrowed down with the extra runner arguments.
+pub struct LlvmCovRunner {
+    runner_args: Vec<String>,
+}
+
+/// Extract the line coverage percentage from the `TOTAL` row of
+/// `cargo llvm-cov report --summary-only`, whose percentage columns are regions,
+/// functions, lines and, if measured, branches
+fn parse_llvm_cov_total(summary: &str) -> Option<f64> {
+    summary
+        .lines()
+        .find(|line| line.trim_start().starts_with("TOTAL"))?
+        .split_whitespace()
+        .filter_map(|column| column.strip_suffix('%'))
+        .nth(2)?
+        .parse()
+        .ok()
+}
+
+impl CoverageRunner for LlvmCovRunner {
+    fn verify_tools(&self) -> bool {
+        let found = succeeds(&CoverageCommand::new("cargo", ["llvm-cov", "--version"]));
+        if !found {
+            warn!("cargo-llvm-cov not installed; run `cargo install cargo-llvm-cov`");
+        }
+        found
+    }
+
+    fn commands(&self, _test_paths: &[String]) -> Vec<CoverageCommand> {
+        let args = ["llvm-cov", "--html"]
+            .into_iter()
+            .map(String::from)
+            .chain(self.runner_args.iter().cloned());
+        vec![CoverageCommand::new("cargo", args)]
+    }
+
+    fn parse_total(&self) -> io::Result<Option<f64>> {
+        let output = Command::new("cargo")
+            .args(["llvm-cov", "report", "--summary-only"])
+            .output()?;
+
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "cargo llvm-cov report exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(parse_llvm_cov_total(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+}
+
+/// Where Jest writes its coverage reports, with the HTML one in `lcov-report` below it
+const JEST_COVERAGE_DIR: &str = "coverage";
+
+/// Jest with its built-in coverage. The test paths are passed on as test path patterns.
+pub struct JestRunner {
+    runner_args: Vec<String>,
+}
+
+impl CoverageRunner for JestRunner {
+    fn verify_tools(&self) -> bool {
+        let found = succeeds(&CoverageCommand::new(
+            "npx",
+            ["--no-install", "jest", "--version"],
+        ));
+        if !found {
+            warn!("jest not installed; run `npm install --save-dev jest`");
+        }
+        found
+    }
+
+    fn commands(&self, test_paths: &[String]) -> Vec<CoverageCommand> {
+        let args = [
+            "jest",
+            "--coverage",
+            "--coverageDirectory",
+            JEST_COVERAGE_DIR,
+            "--coverageReporters=lcov",
+            "--coverageReporters=json-summary",
+            "--coverageReporters=text",
+        ]
+        .into_iter()
+        .map(String::from)
+        .chain(test_paths.iter().cloned())
+        .chain(self.runner_args.iter().cloned());
+        vec![CoverageCommand::new("npx", args)]
+    }
+
+    fn parse_total(&self) -> io::Result<Option<f64>> {
+        let path = Path::new(JEST_COVERAGE_DIR).join("coverage-summary.json");
+        let summary: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(path)?).map_err(io::Error::other)?;
+        // `pct` is the string "Unknown" when there were no lines to cover
+        Ok(summary["total"]["lines"]["pct"].as_f64())
+    }
+}
+
+/// Commands given with `--cmd-template`, split into words the way a shell would and
+/// separated by `&&`. Placeholders are substituted word by word, so a test path with
+/// spaces still ends up as a single argument, and a word that is just `{path}` expands
+/// to one argument per test path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandTemplate {
+    commands: Vec<Vec<String>>,
+}
+
+impl FromStr for CommandTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words = shlex::split(s).ok_or("unbalanced quotes")?;
+        let commands: Vec<Vec<String>> = words
+            .split(|word| word == "&&")
+            .map(<[String]>::to_vec)
+            .collect();
+        if commands.iter().any(Vec::is_empty) {
+            return Err("empty command".to_string());
+        }
+        Ok(Self { commands })
+    }
+}
+
+impl CommandTemplate {
+    /// Whether any of the commands runs the Python interpreter through `{python}`
+    pub fn uses_python(&self) -> bool {
+        self.commands
+            .iter()
+            .flatten()
+            .any(|word| word.contains("{python}"))
+    }
+}
+
+/// Runs the commands of a `--cmd-template`, leaving tool checks and reading the total
+/// to the runner selected with `--runner`
+pub struct TemplateRunner {
+    template: CommandTemplate,
+    python: CoverageCommand,
+    report_dir: String,
+    inner: Box<dyn CoverageRunner>,
+}
+
+impl CoverageRunner for TemplateRunner {
+    fn verify_tools(&self) -> bool {
+        self.inner.verify_tools()
+    }
+
+    fn commands(&self, test_paths: &[String]) -> Vec<CoverageCommand> {
+        let joined_paths = test_paths.join(" ");
+        let substitute = |word: &String| -> Vec<String> {
+            if word == "{path}" {
+                return test_paths.to_vec();
+            }
+            // The interpreter can take several words, such as `poetry run python`
+            if word == "{python}" {
+                return iter::once(&self.python.program)
+                    .chain(&self.python.args)
+                    .cloned()
+                    .collect();
+            }
+            vec![
+                word.replace("{path}", &joined_paths)
+                    .replace("{python}", &self.python.to_string())
+                    .replace("{dir}", &self.report_dir),
+            ]
+        };
+        self.template
+            .commands
+            .iter()
+            .filter_map(|words| {
+                let mut words = words.iter().flat_map(substitute);
+                let program = words.next()?;
+                Some(CoverageCommand::new(&program, words))
+            })
+            .collect()
+    }
+
+    fn parse_total(&self) -> io::Result<Option<f64>> {
+        self.inner.parse_total()
+    }
+
+    fn file_coverage(&self) -> io::Result<Vec<(String, f64)>> {
+        self.inner.file_coverage()
+    }
+
+    fn parse_test_counts(&self, stdout: &str) -> Option<TestCounts> {
+        self.inner.parse_test_counts(stdout)
+    }
+}
+
+/// Coverage runner selected by `--runner`, running the `--cmd-template` commands
+/// instead of its own if one is given
+pub fn from_args(python: &CoverageCommand, args: &Args) -> Box<dyn CoverageRunner> {
+    let runner = runner_from_args(python, args);
+    match &args.cmd_template {
+        Some(template) => Box::new(TemplateRunner {
+            template: template.clone(),
+            python: python.clone(),
+            report_dir: args.dir.clone(),
+            inner: runner,
+        }),
+        None => runner,
+    }
+}
+
+fn runner_from_args(python: &CoverageCommand, args: &Args) -> Box<dyn CoverageRunner> {
+    let python_runner = |test_runner| {
+        // pytest-xdist is a pytest plugin, unittest runs serially
+        let jobs = args
+            .jobs
+            .filter(|_| test_runner == PythonTestRunner::Pytest);
+        Box::new(PythonCoverageRunner {
+            python: python.clone(),
+            test_runner,
+            runner_args: args.runner_args.clone(),
+            report_dir: args.dir.clone(),
+            erase: args.erase,
+            jobs,
+            xml_report: args.xml.clone(),
+            lcov_report: args.lcov.clone(),
+            branch: args.branch,
+            context: args.context.clone(),
+            runs: args.runs.clone(),
+            omit: (!args.exclude.is_empty()).then(|| args.exclude.join(",")),
+        })
+    };
+
+    match args.runner {
+        TestRunner::Pytest => python_runner(PythonTestRunner::Pytest),
+        TestRunner::Unittest => python_runner(PythonTestRunner::Unittest),
+        TestRunner::LlvmCov => Box::new(LlvmCovRunner {
+            runner_args: args.runner_args.clone(),
+        }),
+        TestRunner::Jest => Box::new(JestRunner {
+            runner_args: args.runner_args.clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(stdout: &str) -> Option<String> {
+        parse_pytest_summary(stdout).map(|counts| counts.to_string())
+    }
+
+    #[test]
+    fn strip_ansi_removes_colors() {
+        assert_eq!(strip_ansi("\x1B[32m\x1B[1m3 passed\x1B[0m"), "3 passed");
+        assert_eq!(strip_ansi("no colors"), "no colors");
+    }
+
+    #[test]
+    fn pytest_summary_is_found_across_versions() {
+        let colored = "\x1B[32m===== \x1B[32m\x1B[1m3 passed\x1B[0m, \x1B[33m1 skipped\x1B[0m\
+                       \x1B[32m in 0.12s\x1B[0m\x1B[32m =====\x1B[0m";
+        assert_eq!(summary(colored).unwrap(), "3 passed, 1 skipped");
+        assert_eq!(
+            summary("============ no tests ran in 0.01s ============").unwrap(),
+            "no tests ran"
+        );
+        assert_eq!(summary("===== 1 error in 0.05s =====").unwrap(), "1 error");
+        // Older versions print the duration in seconds without a unit
+        assert_eq!(
+            summary("== 1 failed, 2 passed in 1.50 seconds ==").unwrap(),
+            "1 failed, 2 passed"
+        );
+    }
+
+    #[test]
+    fn pytest_summary_skips_the_lines_around_it() {
+        let stdout = "tests/test_a.py ..F\n\
+                      ===== 1 failed, 2 passed in 0.30s =====\n\
+                      Wrote HTML report to htmlcov/index.html\n";
+        assert_eq!(summary(stdout).unwrap(), "1 failed, 2 passed");
+        let stdout = "===== 4 passed in 0.10s =====\n\
+                      2 files skipped due to complete coverage in 0.5s\n";
+        assert_eq!(summary(stdout).unwrap(), "4 passed");
+        assert_eq!(summary("Ran 3 tests in 0.001s\n\nOK\n"), None);
+    }
+
+    #[test]
+    fn llvm_cov_total_is_the_line_coverage() {
+        let summary = "\
+Filename    Regions  Missed Regions  Cover  Functions  Missed Functions  Executed  Lines  Missed Lines  Cover  Branches  Missed Branches  Cover
+src/main.rs      40               4  90.00%        10                 1    90.00%    100            25  75.00%         0                0      -
+TOTAL            40               4  90.00%        10                 1    90.00%    100            25  75.00%         0                0      -
+";
+        assert_eq!(parse_llvm_cov_total(summary), Some(75.0));
+        assert_eq!(
+            parse_llvm_cov_total("error: no coverage data found\n"),
+            None
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Notify;
+
+/// Shutdown signal shared by the server, the REPL, the watcher and the Ctrl+C handler.
+/// Blocking loops check it between iterations, async tasks wait on it.
+pub struct Shutdown {
+    running: AtomicBool,
+    notify: Notify,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            running: AtomicBool::new(true),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Ask everything to stop, waking up the tasks waiting in `requested`
+    pub fn trigger(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    /// Wait until shutdown is triggered, returning right away if it already was
+    pub async fn requested(&self) {
+        // Created before checking the flag so a trigger in between isn't missed
+        let notified = self.notify.notified();
+        if !self.is_running() {
+            return;
+        }
+        notified.await;
+    }
+}
@@ -0,0 +1,189 @@
+use crate::api::ReportDir;
+use actix_web::{HttpResponse, http::header::ContentType, web};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, fs, io, path::Path};
+
+/// Directory in the report directory holding the snapshots, one subdirectory each
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+
+/// File in each snapshot recording what is known about its run
+const INFO_FILE: &str = "snapshot.json";
+
+/// A snapshot of the report of one run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Time of the run, also the name of its directory
+    #[serde(skip)]
+    pub name: String,
+    /// Total coverage of the run, if it could be read
+    pub total: Option<f64>,
+    /// Abbreviated hash of the commit the run tested, if in a git repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+/// Copy the contents of `from` into `to`, recursively, leaving out `skip`
+fn copy_dir(from: &Path, to: &Path, skip: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path == skip {
+            continue;
+        }
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&path, &target, skip)?;
+        } else {
+            fs::copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copy the report in `report_dir` into a new snapshot named after the current time,
+/// recording the run's `total` and `commit` with it, then delete the oldest snapshots so
+/// only the latest `keep` remain. Returns the name of the new snapshot, which is also
+/// its path below `/snapshots/` on the server.
+pub fn take(
+    report_dir: &Path,
+    keep: usize,
+    total: Option<f64>,
+    commit: Option<String>,
+) -> io::Result<String> {
+    let snapshots_dir = report_dir.join(SNAPSHOTS_DIR);
+    // Sorts chronologically and is valid in a URL and on every file system
+    let name = Local::now().format("%Y-%m-%dT%H-%M-%S").to_string();
+    let snapshot = snapshots_dir.join(&name);
+    // Two runs within the same second keep the later report
+    if snapshot.exists() {
+        fs::remove_dir_all(&snapshot)?;
+    }
+    copy_dir(report_dir, &snapshot, &snapshots_dir)?;
+    let info = Snapshot {
+        name: name.clone(),
+        total,
+        commit,
+    };
+    fs::write(snapshot.join(INFO_FILE), serde_json::to_string(&info)?)?;
+    prune(&snapshots_dir, keep)?;
+    Ok(name)
+}
+
+/// Delete the oldest snapshots in `snapshots_dir` beyond the latest `keep`
+fn prune(snapshots_dir: &Path, keep: usize) -> io::Result<()> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(snapshots_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            names.push(entry.file_name());
+        }
+    }
+    names.sort();
+
+    let excess = names.len().saturating_sub(keep);
+    for name in &names[..excess] {
+        fs::remove_dir_all(snapshots_dir.join(name))?;
+    }
+    Ok(())
+}
+
+/// Snapshots in `report_dir`, newest first. Without any, or without the snapshots
+/// directory, the list is empty.
+pub fn list(report_dir: &Path) -> io::Result<Vec<Snapshot>> {
+    let entries = match fs::read_dir(report_dir.join(SNAPSHOTS_DIR)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut snapshots = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        // A snapshot without readable info is still listed, just without its details
+        let info = fs::read_to_string(entry.path().join(INFO_FILE))
+            .ok()
+            .and_then(|info| serde_json::from_str::<Snapshot>(&info).ok());
+        snapshots.push(Snapshot {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            total: info.as_ref().and_then(|info| info.total),
+            commit: info.and_then(|info| info.commit),
+        });
+    }
+    snapshots.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(snapshots)
+}
+
+/// Escape text for use in HTML content and attribute values
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Page listing the snapshots with their totals and commits, each linking to its report
+fn render_index(snapshots: &[Snapshot]) -> String {
+    let mut items = String::new();
+    for snapshot in snapshots {
+        let name = escape_html(&snapshot.name);
+        let total = snapshot
+            .total
+            .map_or_else(|| "unknown".to_string(), |total| format!("{:.1}%", total));
+        let commit = snapshot
+            .commit
+            .as_ref()
+            .map(|commit| format!(" <code>{}</code>", escape_html(commit)))
+            .unwrap_or_default();
+        let _ = writeln!(
+            items,
+            "        <li><a href=\"/{}/{}/\">{}</a> <span class=\"total\">{}</span>{}</li>",
+            SNAPSHOTS_DIR, name, name, total, commit
+        );
+    }
+    let body = if snapshots.is_empty() {
+        "    <p>No snapshots yet. They are saved after each successful run when the server \
+         is started with <code>--snapshots N</code>.</p>"
+            .to_string()
+    } else {
+        format!("    <ul>\n{}    </ul>", items)
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>Coverage Snapshots</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; margin: 40px; color: #333; }}
+        a {{ color: #3498db; }}
+        li {{ margin: 6px 0; }}
+        .total {{ color: #777; margin-left: 10px; }}
+    </style>
+</head>
+<body>
+    <h1>Coverage snapshots</h1>
+{}
+    <p><a href="/">Current report</a></p>
+</body>
+</html>"#,
+        body
+    )
+}
+
+/// Page listing the snapshots, newest first
+pub async fn index(dir: web::Data<ReportDir>) -> HttpResponse {
+    let dir = dir.0.clone();
+    match web::block(move || list(&dir)).await {
+        Ok(Ok(snapshots)) => HttpResponse::Ok()
+            .content_type(ContentType::html())
+            .body(render_index(&snapshots)),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
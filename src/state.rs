@@ -0,0 +1,16 @@
+use std::{fs, io};
+
+/// File in the current directory remembering the last test path between sessions
+pub const STATE_FILE: &str = ".coverage-http-state";
+
+/// Test path saved by a previous session, if any
+pub fn load_test_path() -> Option<String> {
+    let contents = fs::read_to_string(STATE_FILE).ok()?;
+    let path = contents.trim();
+    (!path.is_empty()).then(|| path.to_string())
+}
+
+/// Remember the test path for the next session
+pub fn save_test_path(path: &str) -> io::Result<()> {
+    fs::write(STATE_FILE, format!("{}\n", path))
+}
@@ -0,0 +1,46 @@
+use rcgen::CertifiedKey;
+use rustls::{
+    ServerConfig,
+    crypto::ring,
+    pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer},
+};
+use sha2::{Digest, Sha256};
+use std::{io, sync::Arc};
+
+/// Names the self-signed certificate is valid for, besides the bind host itself
+const LOCAL_NAMES: &[&str] = &["localhost", "127.0.0.1", "::1"];
+
+/// Generate a self-signed certificate for `host` and build a server configuration
+/// around it. Also returns the certificate's SHA-256 fingerprint so the user can check
+/// it against what the browser shows.
+pub fn self_signed_config(host: &str) -> io::Result<(ServerConfig, String)> {
+    let mut names = vec![host.to_string()];
+    names.extend(
+        LOCAL_NAMES
+            .iter()
+            .filter(|name| **name != host)
+            .map(|name| name.to_string()),
+    );
+    let CertifiedKey { cert, key_pair } =
+        rcgen::generate_simple_self_signed(names).map_err(io::Error::other)?;
+
+    let fingerprint = fingerprint(cert.der());
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key_pair.serialize_der()));
+    let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(io::Error::other)?
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.der().clone()], key)
+        .map_err(io::Error::other)?;
+    Ok((config, fingerprint))
+}
+
+/// SHA-256 of the DER-encoded certificate as colon-separated hex, the way browsers
+/// display it
+fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
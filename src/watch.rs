@@ -0,0 +1,205 @@
+use crate::shutdown::Shutdown;
+use globset::{Glob, GlobMatcher, GlobSet, GlobSetBuilder};
+use ignore::gitignore::Gitignore;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+use tracing::{error, info, warn};
+
+/// How often the watcher checks whether it should stop while no events arrive
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Paths that never trigger a run, whatever the options: virtualenvs, bytecode caches
+/// and git's own files
+const ALWAYS_IGNORED: &[&str] = &[".venv/**", "venv/**", "**/__pycache__/**", ".git/**"];
+
+/// Which of the changed files matching the watched glob trigger a run
+pub struct Filter {
+    /// Extensions without the dot, compared ignoring case. Empty lets any through.
+    extensions: Vec<String>,
+    ignored: GlobSet,
+    /// Rules of the `.gitignore` in the current directory, if it is honored
+    gitignore: Option<Gitignore>,
+}
+
+impl Filter {
+    /// Keep the files with one of `extensions`, with or without their dot, or any if it
+    /// is empty, that match none of the `ignored` globs and, with `use_gitignore`, aren't
+    /// ignored by git
+    pub fn new(extensions: &[String], ignored: &[String], use_gitignore: bool) -> io::Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in ALWAYS_IGNORED
+            .iter()
+            .copied()
+            .chain(ignored.iter().map(String::as_str))
+        {
+            let glob = Glob::new(pattern.trim_start_matches("./"))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            builder.add(glob);
+        }
+        Ok(Self {
+            extensions: extensions
+                .iter()
+                .map(|extension| extension.trim().trim_start_matches('.').to_string())
+                .collect(),
+            ignored: builder.build().map_err(io::Error::other)?,
+            gitignore: use_gitignore.then(load_gitignore),
+        })
+    }
+
+    /// Whether a change to `path`, relative to the current directory, triggers a run
+    fn allows(&self, path: &Path) -> bool {
+        let has_extension = self.extensions.is_empty()
+            || path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    self.extensions
+                        .iter()
+                        .any(|allowed| allowed.eq_ignore_ascii_case(extension))
+                });
+        let git_ignored = self.gitignore.as_ref().is_some_and(|gitignore| {
+            gitignore
+                .matched_path_or_any_parents(path, false)
+                .is_ignore()
+        });
+        has_extension && !self.ignored.is_match(path) && !git_ignored
+    }
+}
+
+/// Rules of the `.gitignore` in the current directory. Without one there are none, and
+/// lines that can't be parsed are left out with a warning.
+fn load_gitignore() -> Gitignore {
+    if !Path::new(".gitignore").is_file() {
+        return Gitignore::empty();
+    }
+    let (gitignore, error) = Gitignore::new(".gitignore");
+    if let Some(e) = error {
+        warn!("Warning: ignoring part of .gitignore: {}", e);
+    }
+    gitignore
+}
+
+/// Extension the files matching a glob must have, such as `html` for
+/// `templates/**/*.html`, if its last component spells one out
+pub fn glob_extension(pattern: &str) -> Option<&str> {
+    let name = pattern.rsplit('/').next()?;
+    let (_, extension) = name.rsplit_once('.')?;
+    let is_literal = !extension.is_empty() && !extension.contains(['*', '?', '[', ']', '{', '}']);
+    is_literal.then_some(extension)
+}
+
+/// Split a glob into the directory to watch and a matcher for paths relative to the
+/// current directory. A plain directory watches every Python file below it.
+fn parse_pattern(pattern: &str) -> io::Result<(PathBuf, GlobMatcher)> {
+    let mut pattern = pattern.trim_start_matches("./").to_string();
+    let is_glob = |s: &str| s.contains(['*', '?', '[', '{']);
+    if !is_glob(&pattern) && Path::new(&pattern).is_dir() {
+        pattern = format!("{}/**/*.py", pattern.trim_end_matches('/'));
+    }
+
+    let root: PathBuf = pattern
+        .split('/')
+        .take_while(|component| !is_glob(component))
+        .collect();
+    let root = if root.as_os_str().is_empty() || root == Path::new(&pattern) {
+        root.parent().map(Path::to_path_buf).unwrap_or_default()
+    } else {
+        root
+    };
+    let root = if root.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        root
+    };
+
+    let matcher = Glob::new(&pattern)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+        .compile_matcher();
+    Ok((root, matcher))
+}
+
+/// Watch files matching `pattern` and let through by `filter`, and call `on_change` once
+/// per burst of changes, until shutdown is triggered. Events arriving within `debounce`
+/// of each other are coalesced into a single call, and so are all events queued up
+/// while `on_change` was running. A call is held back until `min_interval` has passed
+/// since the previous one returned.
+pub fn watch(
+    pattern: &str,
+    filter: &Filter,
+    debounce: Duration,
+    min_interval: Duration,
+    shutdown: &Shutdown,
+    mut on_change: impl FnMut(),
+) -> io::Result<()> {
+    let (root, matcher) = parse_pattern(pattern)?;
+    let cwd = env::current_dir()?.canonicalize()?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(io::Error::other)?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(io::Error::other)?;
+    info!("Watching {} for changes to {}", root.display(), pattern);
+
+    let is_relevant = |event: &notify::Result<notify::Event>| match event {
+        Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+            event.paths.iter().any(|path| {
+                let relative = path.strip_prefix(&cwd).unwrap_or(path);
+                let relative = relative.strip_prefix(".").unwrap_or(relative);
+                matcher.is_match(relative) && filter.allows(relative)
+            })
+        }
+        Ok(_) => false,
+        Err(e) => {
+            error!("Watch error: {}", e);
+            false
+        }
+    };
+
+    let mut last_run_end: Option<Instant> = None;
+    while shutdown.is_running() {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(event) if is_relevant(&event) => {}
+            Ok(_) | Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        // Wait for the burst to settle before running
+        while rx.recv_timeout(debounce).is_ok() {}
+        if !shutdown.is_running() {
+            break;
+        }
+
+        // Too soon after the last run, such as for files the run itself wrote
+        if let Some(ready) = last_run_end.map(|end| end + min_interval)
+            && let Some(wait) = ready.checked_duration_since(Instant::now())
+        {
+            info!(
+                "Change detected, waiting {:.1}s before re-running (--watch-min-interval)",
+                wait.as_secs_f64()
+            );
+            // Changes arriving meanwhile are covered by the run that follows
+            while let Some(remaining) = ready.checked_duration_since(Instant::now()) {
+                if !shutdown.is_running() {
+                    return Ok(());
+                }
+                if let Err(RecvTimeoutError::Disconnected) =
+                    rx.recv_timeout(remaining.min(POLL_INTERVAL))
+                {
+                    return Ok(());
+                }
+            }
+        }
+
+        info!("Change detected, re-running coverage");
+        on_change();
+        last_run_end = Some(Instant::now());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// POST /api/shutdown stops the tool gracefully while the prompt is waiting for a line,
+/// without anything being typed and without the forced exit
+#[test]
+fn api_shutdown_stops_an_idle_prompt() {
+    let dir = std::env::temp_dir().join(format!("coverage-http shutdown {}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // Stdin stays open and empty, like a terminal nobody types in, and with no forced
+    // exit only the graceful stop can end the process
+    let mut child = Command::new(env!("CARGO_BIN_EXE_coverage-http"))
+        .args(["--port", "0", "--no-persist", "--allow-run"])
+        .args(["--cmd-template", "true", "--shutdown-timeout", "0"])
+        .current_dir(&dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let (lines, received) = mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let _ = lines.send(line);
+        }
+    });
+
+    // Serving, and at the prompt
+    let mut port = None;
+    let mut prompting = false;
+    while port.is_none() || !prompting {
+        let line = received.recv_timeout(Duration::from_secs(10)).unwrap();
+        if let Some(url) = line.strip_prefix("Starting HTTP server on http://localhost:") {
+            port = Some(url.trim_end_matches('/').parse::<u16>().unwrap());
+        }
+        prompting |= line.starts_with("Current test path:");
+    }
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port.unwrap())).unwrap();
+    stream
+        .write_all(b"POST /api/shutdown HTTP/1.0\r\nContent-Length: 0\r\n\r\n")
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.0 202"), "{}", response);
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if Instant::now() > deadline {
+            child.kill().unwrap();
+            panic!("the tool didn't stop after POST /api/shutdown");
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    drop(child.stdin.take());
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(status.success(), "{}", status);
+    // Everything the tool printed, now that its stdout is closed
+    let output: Vec<String> = received.iter().collect();
+    assert!(
+        output
+            .iter()
+            .any(|line| line == "Graceful shutdown complete")
+    );
+}